@@ -4,6 +4,7 @@ use crate::map::{
     tile::{Los, Passable},
     utils::Coordinate,
 };
+use crate::resources::id::BranchID;
 
 pub mod attributes;
 pub mod behavior;
@@ -14,6 +15,7 @@ pub mod health;
 pub mod image;
 pub mod items;
 pub mod spell;
+pub mod stats;
 pub mod tags;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +27,56 @@ pub struct Collision(pub Passable);
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct SightBlocking(pub Los);
 
+// Where a staircase leads. The main line of the dungeon is branch 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Destination {
+    pub branch: BranchID,
+    pub depth: u32,
+}
+
+// Lights up the tiles around the entity, see map::lighting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LightSource {
+    pub radius: u32,
+}
+
+// The kinds of terrain an entity can cross. Entities without it only walk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Movement {
+    pub walk: bool,
+    pub swim: bool,
+    pub fly: bool,
+}
+
+impl Default for Movement {
+    fn default() -> Self {
+        Self {
+            walk: true,
+            swim: false,
+            fly: false,
+        }
+    }
+}
+
+impl Movement {
+    // Anything flying passes over every tile that isn't solid, projectiles included.
+    pub const FLYING: Movement = Movement {
+        walk: true,
+        swim: false,
+        fly: true,
+    };
+
+    pub fn can_cross(&self, passable: Passable) -> bool {
+        match passable {
+            Passable::Walk => self.walk || self.fly,
+            Passable::Swim => self.swim || self.fly,
+            Passable::Fly => self.fly,
+            Passable::None => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Position(pub Coordinate);
 
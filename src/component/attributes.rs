@@ -5,6 +5,11 @@ use super::combat::{Attack, AttackType};
 const MIGHT_ATTACK_BONUS: f64 = 2.4;
 const SKILL_ATTACK_BONUS: f64 = 1.6;
 const WIT_DAMAGE_BONUS: f64 = 3.6;
+const SKILL_SEARCH_BONUS: f64 = 0.05;
+const ACTIVE_SEARCH_CHANCE: f64 = 0.3;
+const PASSIVE_SEARCH_CHANCE: f64 = 0.05;
+const SKILL_STEALTH_BONUS: f64 = 0.08;
+const MAX_STEALTH: f64 = 0.6;
 
 pub const ATTRIBUTE_MINIMUM: u32 = 1; 
 
@@ -82,3 +87,18 @@ pub fn spell_damage_boost(stats: &Attributes) -> u32 {
 pub fn next_level_requirement(level: u32) -> u32 {
     (0..=level).sum::<u32>() * 100
 }
+
+// Chance to find a secret door within reach. Searching on purpose beats noticing one in passing.
+pub fn search_chance(stats: &Attributes, deliberate: bool) -> f64 {
+    let base_chance = match deliberate {
+        true => ACTIVE_SEARCH_CHANCE,
+        false => PASSIVE_SEARCH_CHANCE,
+    };
+
+    (base_chance + stats.skill as f64 * SKILL_SEARCH_BONUS).min(1.0)
+}
+
+// Part of a monster's chance to notice the player that the player's skill takes away.
+pub fn stealth(stats: &Attributes) -> f64 {
+    (stats.skill as f64 * SKILL_STEALTH_BONUS).min(MAX_STEALTH)
+}
@@ -1,7 +1,18 @@
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 
+use crate::rng::game_rng;
 use crate::{
-    event::combat_events::{AttackEvent, ShootEvent}, map::{los::line_of_sight, GameMap}, resources::ResourceManager, world::World
+    event::combat_events::{AttackEvent, ShootEvent},
+    map::{
+        lighting,
+        los::{cover_on_line, line_of_sight, Cover},
+        pathfinding::{astar_heuristic_factory, pathfind},
+        utils::{Coordinate, DOWN, LEFT, RIGHT, UP},
+        GameMap,
+    },
+    resources::ResourceManager,
+    world::World,
 };
 
 use super::{
@@ -9,6 +20,7 @@ use super::{
     Position,
 };
 use crate::error::{Error, Result};
+use crate::system::definitions;
 use crate::system::error::MonsterTurnError;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -16,6 +28,11 @@ pub struct Behavior {
     pub state: BehaviorState,
     pub behavior: BehaviorName,
     pub action_count: u32,
+    // Where the monster last noticed the player, kept up to date by the monster_perception system.
+    // Awake monsters that lost sight of the player search there before they give up and wander.
+    pub last_known_position: Option<Coordinate>,
+    // Actions still owed for stepping onto slow ground like mud, the way the player loses extra turns there.
+    pub move_debt: u32,
 }
 
 impl Behavior {
@@ -32,6 +49,8 @@ impl Behavior {
             state: BehaviorState::Awake,
             behavior: BehaviorName::AggressiveMelee,
             action_count: 1,
+            last_known_position: None,
+            move_debt: 0,
         }
     }
 
@@ -40,6 +59,8 @@ impl Behavior {
             state: BehaviorState::Awake,
             behavior: BehaviorName::AggressiveRanged,
             action_count: 1,
+            last_known_position: None,
+            move_debt: 0,
         }
     }
 
@@ -48,6 +69,8 @@ impl Behavior {
             state: BehaviorState::Awake,
             behavior: BehaviorName::AggressiveMelee,
             action_count: 2,
+            last_known_position: None,
+            move_debt: 0,
         }
     }
 
@@ -59,6 +82,10 @@ impl Behavior {
         resources: &ResourceManager,
     ) -> Result<()> {
         for _ in 0..self.action_count {
+            if pay_move_debt(own_entity, world) {
+                continue;
+            }
+
             let action = self.behavior.choose_action(own_entity, self.state, world, map, resources)?;
             action.perform(own_entity, world, map, resources)?;
         }
@@ -76,6 +103,7 @@ pub enum AIAction {
     Wander,
     Flee,
     WakeUp,
+    Investigate(Coordinate),
 }
 
 impl AIAction {
@@ -91,6 +119,8 @@ impl AIAction {
             AIAction::Attack(target) => attack_entity(own_entity, *target, world, map, resources),
             AIAction::Shoot(target) => shoot_entity(own_entity, *target, world, map, resources),
             AIAction::WakeUp => wake_up(own_entity, world),
+            AIAction::Investigate(target) => investigate(own_entity, *target, world, map, resources),
+            AIAction::Wander => wander(own_entity, world, map, resources),
             AIAction::Sleep => Ok(()),
             _ => Err(MonsterTurnError::FailedToCompleteAction {
                 entity: own_entity,
@@ -143,14 +173,15 @@ impl BehaviorName {
 fn approach_player(
     own_entity: usize,
     world: &mut World,
-    map: &GameMap,
-    _resources: &ResourceManager,
+    map: &mut GameMap,
+    resources: &ResourceManager,
 ) -> Result<()> {
     let Some(Position(origin)) = world.borrow_entity_component::<Position>(own_entity) else {
         return Err(MonsterTurnError::NoPositionFound { entity: own_entity }.into());
     };
 
-    let Some(step) = map.pathing_grid.get(origin) else {
+    let movement = world.get_movement(own_entity);
+    let Some(step) = map.get_pathing_step(*origin, movement) else {
         return Err(MonsterTurnError::NoPathfindingData {
             entity: own_entity,
             coordinate: *origin,
@@ -158,14 +189,43 @@ fn approach_player(
         .into());
     };
 
-    let destination = *origin + *step;
+    let destination = *origin + step;
     if world.get_blocking_entity(destination).is_none() {
-        world.update_position(own_entity, destination);
+        step_to(own_entity, destination, world, map, resources)?;
     }
 
     Ok(())
 }
 
+// Moves onto the tile and runs up a debt for every action the tile takes beyond the first.
+fn step_to(
+    own_entity: usize,
+    destination: Coordinate,
+    world: &mut World,
+    map: &mut GameMap,
+    resources: &ResourceManager,
+) -> Result<()> {
+    let movement = world.get_movement(own_entity);
+    let cost = map.get_movement_cost(destination, movement, resources);
+
+    world.update_position(own_entity, destination);
+    if let Some(behavior) = world.borrow_entity_component_mut::<Behavior>(own_entity) {
+        behavior.move_debt = cost - 1;
+    }
+    definitions::enter_tile(own_entity, world, map, resources)
+}
+
+// Spends an action on paying back the move debt, if there is any.
+fn pay_move_debt(own_entity: usize, world: &mut World) -> bool {
+    match world.borrow_entity_component_mut::<Behavior>(own_entity) {
+        Some(behavior) if behavior.move_debt > 0 => {
+            behavior.move_debt -= 1;
+            true
+        }
+        _ => false,
+    }
+}
+
 fn attack_entity(
     source: usize,
     target: usize,
@@ -184,7 +244,11 @@ fn shoot_entity(
     map: &mut GameMap,
     resources: &ResourceManager,
 ) -> Result<()> {
-    world.send_event(map, resources, &ShootEvent::new(source), target)?;
+    let cover = match (world.borrow_entity_component::<Position>(source), world.borrow_entity_component::<Position>(target)) {
+        (Some(Position(origin)), Some(Position(destination))) => cover_on_line(*origin, *destination, map, world, resources),
+        _ => Cover::None,
+    };
+    world.send_event(map, resources, &ShootEvent::new(source).with_cover(cover), target)?;
     Ok(())
 }
 
@@ -198,6 +262,94 @@ fn wake_up(
     Ok(())
 }
 
+// Walks one step along a path to the place the monster last noticed the player.
+// Forgets about it when there is no way to get there.
+fn investigate(
+    own_entity: usize,
+    target: Coordinate,
+    world: &mut World,
+    map: &mut GameMap,
+    resources: &ResourceManager,
+) -> Result<()> {
+    let Some(Position(origin)) = world.borrow_entity_component::<Position>(own_entity).copied() else {
+        return Err(MonsterTurnError::NoPositionFound { entity: own_entity }.into());
+    };
+
+    let movement = world.get_movement(own_entity);
+    let step = pathfind(
+        origin,
+        target,
+        map,
+        world,
+        resources,
+        astar_heuristic_factory(target),
+        movement,
+        true,
+        false,
+        std::u32::MAX,
+    )
+    .and_then(|mut path| path.next());
+
+    let Some(step) = step else {
+        if let Some(behavior) = world.borrow_entity_component_mut::<Behavior>(own_entity) {
+            behavior.last_known_position = None;
+        }
+        return Ok(());
+    };
+
+    let destination = origin + step;
+    if world.get_blocking_entity(destination).is_none() {
+        step_to(own_entity, destination, world, map, resources)?;
+    }
+
+    Ok(())
+}
+
+// Gives up on the last known position of the player and takes a step in a random direction the monster can safely go.
+fn wander(
+    own_entity: usize,
+    world: &mut World,
+    map: &mut GameMap,
+    resources: &ResourceManager,
+) -> Result<()> {
+    if let Some(behavior) = world.borrow_entity_component_mut::<Behavior>(own_entity) {
+        behavior.last_known_position = None;
+    }
+
+    let Some(Position(origin)) = world.borrow_entity_component::<Position>(own_entity).copied() else {
+        return Err(MonsterTurnError::NoPositionFound { entity: own_entity }.into());
+    };
+
+    let movement = world.get_movement(own_entity);
+    let destinations: Vec<Coordinate> = [UP, DOWN, LEFT, RIGHT]
+        .into_iter()
+        .map(|direction| origin + direction)
+        .filter(|destination| map.is_tile_passable(*destination, movement, resources))
+        .filter(|destination| !map.is_tile_hazardous(*destination, movement, resources))
+        .filter(|destination| world.get_blocking_entity(*destination).is_none())
+        .collect();
+
+    if let Some(destination) = destinations.choose(&mut game_rng()) {
+        step_to(own_entity, *destination, world, map, resources)?;
+    }
+
+    Ok(())
+}
+
+// Awake monsters only go after a player they still notice. Otherwise they search the last place they noticed
+// them, and wander once they got there without finding anyone.
+fn track_player(entity: usize, origin: Coordinate, player_position: Coordinate, world: &World) -> Option<AIAction> {
+    let last_known_position = world
+        .borrow_entity_component::<Behavior>(entity)
+        .and_then(|behavior| behavior.last_known_position);
+
+    match last_known_position {
+        Some(position) if position == player_position => None,
+        Some(position) if position != origin => Some(AIAction::Investigate(position)),
+        _ => Some(AIAction::Wander),
+    }
+}
+
 fn aggressive_melee(
     entity: usize,
     ai_state: BehaviorState,
@@ -227,6 +379,10 @@ fn aggressive_melee(
         return Err(MonsterTurnError::NoPositionFound { entity: player }.into());
     };
 
+    if let Some(action) = track_player(entity, *origin, player_position, world) {
+        return Ok(action);
+    }
+
     let Some(Combat {
         melee_attack: Some(attack),
         ..
@@ -279,6 +435,10 @@ fn aggressive_ranged(
         return Err(MonsterTurnError::NoPositionFound { entity: player }.into());
     };
 
+    if let Some(action) = track_player(entity, *origin, player_position, world) {
+        return Ok(action);
+    }
+
     let Some(Combat {
         ranged_attack: Some(attack),
         ..
@@ -291,7 +451,11 @@ fn aggressive_ranged(
         .into());
     };
 
-    if origin.distance(player_position) <= attack.range
+    // archers only shoot at what they can make out, the rest of the way they close in
+    let spotting_range = lighting::spotting_range(map.get_light_level(player_position));
+    let distance = origin.distance(player_position);
+    if distance <= attack.range
+        && distance <= spotting_range
         && line_of_sight(*origin, player_position, map, world, resources)
     {
         action = AIAction::Shoot(player);
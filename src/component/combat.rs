@@ -1,9 +1,10 @@
 use std::ops::{Add, AddAssign};
 
 use num::Num;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
+use crate::rng::game_rng;
 use crate::game::RANGE_EPSILON;
 
 use super::{attributes::Attributes, items::Inventory};
@@ -66,7 +67,7 @@ impl<T: Num + Copy + AddAssign> AddAssign<DamageRange<T>> for DamageRange<T> {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DamageType {
     Physical,
     Burning,
@@ -217,7 +218,7 @@ pub fn calculate_attack(
         
     };
 
-    let mut damage = thread_rng().gen_range(attack.damage_min..=attack.damage_max) as f64;
+    let mut damage = game_rng().gen_range(attack.damage_min..=attack.damage_max) as f64;
 
     if let Some(stats) = stats {
         damage = super::attributes::attack_damage_boost(damage, attack, stats);
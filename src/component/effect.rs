@@ -1,8 +1,35 @@
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+use super::combat::DamageType;
+
+const BURNING_DAMAGE: u32 = 2;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub enum EffectType {
     Burning,
 }
 
+impl EffectType {
+    pub fn damage_per_turn(&self) -> u32 {
+        match self {
+            EffectType::Burning => BURNING_DAMAGE,
+        }
+    }
+
+    pub fn damage_type(&self) -> DamageType {
+        match self {
+            EffectType::Burning => DamageType::Burning,
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            EffectType::Burning => "fire",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct EffectHandler {
     active_effects: Vec<Effect>,
 }
@@ -20,25 +47,45 @@ impl EffectHandler {
         }
     }
 
+    // Ticks every effect once and drops the ones that ran out.
     pub fn tick_all(&mut self) {
         for effect in self.active_effects.iter_mut() {
             effect.tick(1);
         }
+        self.active_effects.retain(|effect| effect.duration != Duration(0));
+    }
+
+    pub fn has_effect(&self, effect_type: EffectType) -> bool {
+        self.active_effects.iter().any(|effect| effect.effect_type == effect_type)
+    }
+
+    pub fn damaging_effects(&self) -> Vec<EffectType> {
+        self.active_effects
+            .iter()
+            .map(|effect| effect.effect_type)
+            .filter(|effect_type| effect_type.damage_per_turn() > 0)
+            .collect()
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Effect {
-    effect_type: EffectType,
-    duration: Duration,
+    pub effect_type: EffectType,
+    pub duration: Duration,
 }
 
 impl Effect {
+    pub fn new(effect_type: EffectType, duration: u32) -> Self {
+        Self { effect_type, duration: Duration(duration) }
+    }
+
     fn same_type(&self, other: &Effect) -> bool {
         self.effect_type == other.effect_type
     }
 
+    // Refreshes the effect instead of stacking it, so standing in fire doesn't burn forever.
     fn merge_with(&mut self, other: &Effect) {
-        self.duration += other.duration;
+        self.duration = Duration(self.duration.0.max(other.duration.0));
     }
 
     fn tick(&mut self, amount: u32) {
@@ -46,7 +93,7 @@ impl Effect {
     }
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Duration(pub u32);
 
 impl std::ops::Add<Duration> for Duration {
@@ -65,12 +112,12 @@ impl std::ops::AddAssign<Duration> for Duration {
 impl std::ops::Sub<Duration> for Duration {
     type Output = Duration;
     fn sub(self, rhs: Duration) -> Self::Output {
-        Self((self.0 - rhs.0).max(0))
+        Self(self.0.saturating_sub(rhs.0))
     }
 }
 
 impl std::ops::SubAssign<Duration> for Duration {
     fn sub_assign(&mut self, rhs: Duration) {
-        self.0 = (self.0 - rhs.0).max(0)
+        self.0 = self.0.saturating_sub(rhs.0)
     }
 }
@@ -1,4 +1,5 @@
-use rand::{thread_rng, Rng};
+use rand::Rng;
+use crate::rng::game_rng;
 
 pub const LEVEL_UP_MULTIPLIER: f64 = 1.15;
 const DEPTH_MULTIPLIER: f64 = 1.1;
@@ -55,6 +56,6 @@ impl From<Health> for HealthStatus {
 pub fn get_adjusted_health(listed_health: u32, depth: u32) -> u32 {
     let depth_adjusted = listed_health as f64 * DEPTH_MULTIPLIER.powf((depth-1) as f64);
     let random_range = (1.0 - RNG_SPAN)..(1.0 + RNG_SPAN);
-    let randomized = thread_rng().gen_range(random_range) * depth_adjusted;
+    let randomized = game_rng().gen_range(random_range) * depth_adjusted;
     randomized as u32
 }
\ No newline at end of file
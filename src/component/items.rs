@@ -1,8 +1,9 @@
 use std::ops::{Add, AddAssign};
 
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
+use crate::rng::game_rng;
 use super::combat::Attack;
 
 const DEPTH_MULTIPLIER: f64 = 1.2;
@@ -35,6 +36,6 @@ pub fn item_damage_boost(in_damage: f64, _attack: &Attack, _stats: &Inventory) -
 pub fn get_adjusted_coins(listed_coins: u32, depth: u32) -> u32 {
     let depth_adjusted = listed_coins as f64 * DEPTH_MULTIPLIER.powf((depth-1) as f64);
     let random_range = (1.0 - RNG_SPAN)..(1.0 + RNG_SPAN);
-    let randomized = thread_rng().gen_range(random_range) * depth_adjusted;
+    let randomized = game_rng().gen_range(random_range) * depth_adjusted;
     randomized as u32
 }
\ No newline at end of file
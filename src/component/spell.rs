@@ -1,33 +1,46 @@
 use log::{debug, warn};
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
+use crate::rng::game_rng;
 use crate::component::tags::Player;
 use crate::event::argument_names::{ARG_DAMAGE_MULTIPLIER, ARG_DAMAGE_MULTIPLIER_OVERRIDE};
 use crate::event::spell_events::SpellEvent;
 use crate::event::{Event, EventArguments};
 use crate::game::RANGE_EPSILON;
 use crate::logger;
-use crate::map::los::line_of_sight;
+use crate::map::los::{cover_on_line, line_of_sight, Cover};
 use crate::map::utils::Coordinate;
 use crate::map::GameMap;
 use crate::component::Position;
 use crate::resources::id::SpellDefinitionId;
 use crate::resources::serialize_spell::SpellDefinition;
 use crate::resources::ResourceManager;
+use crate::system::definitions::apply_effect;
 use crate::system::TimeSlot;
 use crate::world::World;
 
 use super::attributes::{spell_damage_boost, Attributes};
-use super::combat::DamageRange;
+use super::combat::{DamageRange, DamageType};
+use super::effect::{Effect, EffectType};
 use super::health::Health;
 use super::image::ImageState;
 use super::items::Inventory;
+use super::stats::{self, RunStats};
 use super::Name;
 use crate::error::{Error, Result};
 
 type SpellEffect = fn(SpellEffectArguments) -> Result<()>;
 
+const FIREBALL_RANGE: f64 = 12.0 + RANGE_EPSILON;
+const FIREBALL_RADIUS: f64 = 3.5 + RANGE_EPSILON;
+// Turns anything caught on flammable ground keeps burning.
+const FIREBALL_BURN_DURATION: u32 = 3;
+// The blast also damages the tiles right around the target, and can break walls.
+const FIREBALL_BLAST_RADIUS: f64 = 1.0 + RANGE_EPSILON;
+const FIREBALL_TILE_DAMAGE: u32 = 10;
+const DIG_RANGE: f64 = 1.0 + RANGE_EPSILON;
+
 // a spell creates and broadcasts evets to the correct targets
 // all actual changes should happen in event applications
 // event type is usually a SpellEvent, so that SpellResponses react
@@ -40,6 +53,7 @@ type SpellEffect = fn(SpellEffectArguments) -> Result<()>;
 pub enum SpellEffectName {
     Fireball,
     Scry,
+    Dig,
 }
 
 impl SpellEffectName {
@@ -47,9 +61,19 @@ impl SpellEffectName {
         match self {
             SpellEffectName::Fireball => Ok(fireball),
             SpellEffectName::Scry => Ok(scry),
+            SpellEffectName::Dig => Ok(dig),
             _ => Err("No effect listed for that spell name".into())
         }
     }
+
+    // Spells without a range don't need a visible target, like scry.
+    pub fn range(&self) -> Option<f64> {
+        match self {
+            SpellEffectName::Fireball => Some(FIREBALL_RANGE),
+            SpellEffectName::Scry => None,
+            SpellEffectName::Dig => Some(DIG_RANGE),
+        }
+    }
 }
 
 pub struct SpellEffectArguments<'a> {
@@ -213,8 +237,8 @@ fn scry(args: SpellEffectArguments) -> Result<()> {
 
 fn fireball(args: SpellEffectArguments) -> Result<()> {
     let SpellEffectArguments {source, target, world, map, stats, items, resources } = args;
-    let range = 12.0 + RANGE_EPSILON;
-    let radius = 3.5 + RANGE_EPSILON;
+    let range = FIREBALL_RANGE;
+    let radius = FIREBALL_RADIUS;
 
     if !map.is_tile_explored(target) {
         logger::log_message("Can't cast that in unexplored areas");
@@ -242,12 +266,6 @@ fn fireball(args: SpellEffectArguments) -> Result<()> {
         damage += spell_damage_boost(stats);
     }
 
-    let effect = apply_spell_damage_factory(damage);
-    let event = SpellEvent::new(
-        source,
-        effect,
-    );
-
     let entities_in_range: Vec<usize> = world.get_entities_in_room(target)
         .iter()
         .filter(|entity| {
@@ -264,26 +282,84 @@ fn fireball(args: SpellEffectArguments) -> Result<()> {
         if world.borrow_entity_component::<Player>(entity).is_some() {
             continue;
         }
+        let Some(Position(position)) = world.borrow_entity_component(entity).copied() else {
+            continue;
+        };
+
+        // whatever stands between the blast and the entity shields it
+        let cover = cover_on_line(target, position, map, world, resources);
+        let event = SpellEvent::new(source, apply_spell_damage_factory(damage, DamageType::Burning, cover));
         world.send_event(map, resources, &event, entity)?;
+
+        let is_flammable = map.get_tile_at(position, resources).is_some_and(|tile| tile.flammable);
+        if is_flammable && !world.get_movement(entity).fly {
+            apply_effect(entity, Effect::new(EffectType::Burning, FIREBALL_BURN_DURATION), world)?;
+        }
+    }
+
+    let blast_radius = FIREBALL_BLAST_RADIUS.floor() as i32;
+    let blasted_tiles = (-blast_radius..=blast_radius)
+        .flat_map(|x| (-blast_radius..=blast_radius).map(move |y| target + Coordinate { x, y }))
+        .filter(|coordinate| coordinate.distance(target) <= FIREBALL_BLAST_RADIUS);
+
+    for coordinate in blasted_tiles {
+        let Some(tile) = map.get_tile_at(coordinate, resources) else {
+            continue;
+        };
+        let name = tile.name.clone();
+
+        if map.damage_tile(coordinate, FIREBALL_TILE_DAMAGE, world, resources) && map.is_tile_visible(coordinate) {
+            logger::log_message(&format!("The {} gives way.", name.to_lowercase()));
+        }
+    }
+
+    Ok(())
+}
+
+// Digs out a diggable tile next to the caster.
+fn dig(args: SpellEffectArguments) -> Result<()> {
+    let SpellEffectArguments { source, target, world, map, resources, .. } = args;
+
+    let Some(Position(caster_position)) = world.borrow_entity_component(source).copied() else {
+        return Err("Can't find caster position".into());
+    };
+
+    if caster_position.distance(target) > DIG_RANGE {
+        logger::log_message("Target is out of range");
+        return Err(Error::InvalidTarget);
+    }
+
+    let Some(tile) = map.get_tile_at(target, resources) else {
+        return Err(Error::InvalidTarget);
+    };
+    let name = tile.name.clone();
+
+    if !map.dig_tile(target, world, resources) {
+        logger::log_message("Can't dig through that");
+        return Err(Error::InvalidTarget);
     }
-    
+
+    logger::log_message(&format!("You dig through the {}.", name.to_lowercase()));
+    map.explore_surroundings(target);
     Ok(())
 }
 
-fn apply_spell_damage_factory( damage: DamageRange<u32>) -> impl Fn(EventArguments) -> Result<()>{
+fn apply_spell_damage_factory( damage: DamageRange<u32>, damage_type: DamageType, cover: Cover) -> impl Fn(EventArguments) -> Result<()>{
     move |args| {
-        apply_spell_damage(damage, args)
+        apply_spell_damage(damage, damage_type, cover, args)
     }
 }
 
 fn apply_spell_damage(
     damage: DamageRange<u32>,
+    damage_type: DamageType,
+    cover: Cover,
     event_data: EventArguments,
 ) -> Result<()> {
     let EventArguments { world, map, resources, source, target, args, msg_args } = event_data;
 
     let mut message_addendum = "";
-    let mut damage = thread_rng().gen_range(damage.0..=damage.1) as f64;
+    let mut damage = game_rng().gen_range(damage.0..=damage.1) as f64;
 
     if let Some(dmg_multiplier) = args.get(ARG_DAMAGE_MULTIPLIER_OVERRIDE) {
         damage *= dmg_multiplier;
@@ -303,13 +379,24 @@ fn apply_spell_damage(
             message_addendum = "It seems to have little effect.";
         }
     }
-    let damage = damage as u32;
+    if cover != Cover::None && message_addendum.is_empty() {
+        message_addendum = "Cover absorbs part of the blast.";
+    }
+    let damage = (damage * cover.damage_multiplier()) as u32;
 
     let Some(health) = world.borrow_entity_component_mut::<Health>(target) else {
         return Err("Target has no health component.".into());
     };
     health.sub_current(damage );
-    
+    stats::record_damage(source, target, damage_type, damage, world);
+
+    let caster = world
+        .borrow_entity_component::<Name>(source)
+        .map(|Name(name)| name.clone());
+    if let Some(stats) = world.borrow_entity_component_mut::<RunStats>(target) {
+        stats.last_hit_by = caster;
+    }
+
     let name = world.borrow_entity_component::<Name>(target);
     let msg = logger::generate_take_damage_message(name, damage, &message_addendum);
     logger::log_message(&msg);
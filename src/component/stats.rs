@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::world::World;
+
+use super::combat::DamageType;
+
+// Running tally of what the player has done this run, fed by combat, spell, pickup and death events.
+// Lives on the player entity and is carried over between floors by import_player.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunStats {
+    pub turns: u32,
+    pub kills: HashMap<String, u32>,
+    pub spells_cast: HashMap<String, u32>,
+    pub damage_dealt: HashMap<DamageType, u32>,
+    pub damage_taken: HashMap<DamageType, u32>,
+    pub gold_gained: u32,
+    pub doors_opened: u32,
+    pub floors: Vec<FloorStats>,
+    pub last_hit_by: Option<String>,
+}
+
+// The entity whose hit landed last, so a kill is only credited to whoever dealt it.
+// Terrain, effects and other self inflicted damage clear it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LastAttacker(pub Option<usize>);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FloorStats {
+    pub depth: u32,
+    pub turns: u32,
+    pub tiles_explored: u32,
+}
+
+impl RunStats {
+    pub fn register_kill(&mut self, name: &str) {
+        *self.kills.entry(name.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn register_cast(&mut self, name: &str) {
+        *self.spells_cast.entry(name.to_string()).or_insert(0) += 1;
+    }
+
+    pub fn register_damage_dealt(&mut self, damage_type: DamageType, amount: u32) {
+        *self.damage_dealt.entry(damage_type).or_insert(0) += amount;
+    }
+
+    pub fn register_damage_taken(&mut self, damage_type: DamageType, amount: u32) {
+        *self.damage_taken.entry(damage_type).or_insert(0) += amount;
+    }
+
+    // Gets the stats for the given floor, starting a new entry when the player has moved on.
+    pub fn floor_mut(&mut self, depth: u32) -> &mut FloorStats {
+        let is_new_floor = match self.floors.last() {
+            Some(floor) => floor.depth != depth,
+            None => true,
+        };
+
+        if is_new_floor {
+            self.floors.push(FloorStats {
+                depth,
+                ..Default::default()
+            });
+        }
+
+        self.floors.last_mut().unwrap()
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.kills.values().sum()
+    }
+
+    pub fn total_damage_dealt(&self) -> u32 {
+        self.damage_dealt.values().sum()
+    }
+
+    pub fn total_damage_taken(&self) -> u32 {
+        self.damage_taken.values().sum()
+    }
+
+    pub fn total_tiles_explored(&self) -> u32 {
+        self.floors.iter().map(|floor| floor.tiles_explored).sum()
+    }
+}
+
+// Credits both sides of a hit. Entities without run stats are skipped.
+pub fn record_damage(
+    source: usize,
+    target: usize,
+    damage_type: DamageType,
+    amount: u32,
+    world: &mut World,
+) {
+    if source != target {
+        if let Some(stats) = world.borrow_entity_component_mut::<RunStats>(source) {
+            stats.register_damage_dealt(damage_type, amount);
+        }
+    }
+
+    if let Some(stats) = world.borrow_entity_component_mut::<RunStats>(target) {
+        stats.register_damage_taken(damage_type, amount);
+    }
+
+    let attacker = (source != target).then_some(source);
+    if let Some(last_attacker) = world.borrow_entity_component_mut::<LastAttacker>(target) {
+        last_attacker.0 = attacker;
+    } else if attacker.is_some() {
+        if let Err(error) = world.add_component(target, LastAttacker(attacker)) {
+            warn!("Could not record the attacker of {target}: {error}");
+        }
+    }
+}
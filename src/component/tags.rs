@@ -14,6 +14,11 @@ impl Tag for Player {}
 pub struct Door;
 impl Tag for Door {}
 
+// A door disguised as wall until someone finds it.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Secret;
+impl Tag for Secret {}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct StairsDown;
 impl Tag for StairsDown {}
@@ -21,3 +26,17 @@ impl Tag for StairsDown {}
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Hazard;
 impl Tag for Hazard {}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Exit;
+impl Tag for Exit {}
+
+// Marks the artifact on the final floor, and whoever carries it after pickup.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Artifact;
+impl Tag for Artifact {}
+
+// Has to be defeated before a special level lets the player move on.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Boss;
+impl Tag for Boss {}
@@ -18,6 +18,8 @@ pub enum Error {
     #[from]
     SerdeYaml(serde_yaml::Error),
     #[from]
+    SerdeJson(serde_json::Error),
+    #[from]
     Io(std::io::Error),
 }
 
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
+use crate::rng::game_rng;
 use crate::map::GameMap;
 use crate::resources::ResourceManager;
 use crate::world::World;
@@ -66,7 +67,9 @@ pub enum ResponseFuctionName {
     OpenChest,
     RevealRoom,
     Close,
+    Search,
     Pickup,
+    TakeArtifact,
     GrantLevelUp,
     DropInventory,
 }
@@ -84,7 +87,7 @@ impl ResponseFuctionName {
             Self::Default => Ok(response_functions::respond_default),
             Self::ReflectAll => Ok(response_functions::respond_reflect),
             Self::ReflectSome(chance) => 
-                match thread_rng().gen_bool(*chance) {
+                match game_rng().gen_bool(*chance) {
                     true => Ok(response_functions::respond_reflect),
                     false => Ok(response_functions::respond_default),
                 },
@@ -92,7 +95,9 @@ impl ResponseFuctionName {
             Self::OpenChest => Ok(response_functions::respond_open_chest),
             Self::DropInventory => Ok(response_functions::respond_drop),
             Self::Close => Ok(response_functions::respond_close),
+            Self::Search => Ok(response_functions::respond_search),
             Self::Pickup => Ok(response_functions::respond_pickup),
+            Self::TakeArtifact => Ok(response_functions::respond_take_artifact),
             Self::GrantLevelUp => Ok(response_functions::respond_levelup),
             Self::RevealRoom => Ok(response_functions::respond_reveal_room),
             _ => Err("No callable registered for this name".into()),
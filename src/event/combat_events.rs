@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::{
-    component::{self, attributes::Attributes, combat::Combat, health::Health, items::Inventory, responses::{AttackResponse, DeathResponse, ShootResponse}, Name}, error::Result, logger, map::GameMap, resources::ResourceManager, world::World
+    component::{self, attributes::Attributes, combat::Combat, health::Health, items::Inventory, responses::{AttackResponse, DeathResponse, ShootResponse}, stats::{self, RunStats}, Name}, error::Result, logger, map::{los::Cover, GameMap}, resources::ResourceManager, world::World
 };
 use super::{argument_names::{ARG_DAMAGE_MULTIPLIER, ARG_DAMAGE_MULTIPLIER_OVERRIDE, MSG_ARG_ADDENDUM, MSG_ARG_ADDENDUM_OVERRIDE, MSG_ARG_ATTACKER, MSG_ARG_ATTACK_MESSAGE}, Event, EventArguments};
 
@@ -26,6 +26,7 @@ impl Event for AttackEvent {
             event_data.source,
             event_data.target,
             component::combat::calculate_melee_attack,
+            Cover::None,
             event_data.args,
             event_data.msg_args,
         )
@@ -39,11 +40,18 @@ impl Event for AttackEvent {
 
 pub struct ShootEvent {
     pub source: usize,
+    pub cover: Cover,
 }
 
 impl ShootEvent {
     pub fn new(source: usize) -> Self {
-        Self { source }
+        Self { source, cover: Cover::None }
+    }
+
+    // The target's cover against the shooter, which lowers the damage.
+    pub fn with_cover(mut self, cover: Cover) -> Self {
+        self.cover = cover;
+        self
     }
 }
 
@@ -58,6 +66,7 @@ impl Event for ShootEvent {
             event_data.source,
             event_data.target,
             component::combat::calculate_ranged_attack,
+            self.cover,
             event_data.args,
             event_data.msg_args,
         )
@@ -91,6 +100,7 @@ fn apply_attack(
     source: usize,
     target: usize,
     attack: component::combat::AttackFunction,
+    cover: Cover,
     args: &HashMap<String, f64>,
     msg_args: &HashMap<String, String>,
 ) -> Result<()> {
@@ -120,8 +130,9 @@ fn apply_attack(
     let Some(ref mut health) = health_vec[target] else {
         return Err("Defender has no health component".into());
     };
-    let damage = (damage * multiplier) as u32;
+    let damage = (damage * multiplier * cover.damage_multiplier()) as u32;
     health.sub_current(damage);
+    stats::record_damage(source, target, attack_report.damage_type, damage, world);
 
     let mut attacker_name = world.borrow_entity_component::<Name>(source);
     let defender_name = world.borrow_entity_component::<Name>(target);
@@ -148,6 +159,8 @@ fn apply_attack(
             message_addendum = "It's very effective.";
         } else if multiplier < 0.95 {
             message_addendum = "It seems to have little effect.";
+        } else if cover != Cover::None {
+            message_addendum = "Cover absorbs part of the hit.";
         }
     }
 
@@ -158,5 +171,10 @@ fn apply_attack(
         damage);
     logger::log_message(&log_msg);
 
+    let attacker = attacker_name.map(|Name(name)| name.clone());
+    if let Some(stats) = world.borrow_entity_component_mut::<RunStats>(target) {
+        stats.last_hit_by = attacker;
+    }
+
     Ok(())
 }
\ No newline at end of file
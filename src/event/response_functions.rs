@@ -1,11 +1,13 @@
 use std::collections::HashMap;
 
 use log::debug;
+use rand::Rng;
 
+use crate::rng::game_rng;
 use crate::{
     component::{
-        attributes::Xp, responses::InteractResponse, image::ImageState, items::Coins, Collision,
-        Position, SightBlocking,
+        attributes::{self, Attributes, Xp}, responses::InteractResponse, image::ImageState, items::Coins,
+        stats::RunStats, tags::{Artifact, Secret}, Collision, Position, SightBlocking,
     },
     logger,
     map::{
@@ -54,9 +56,12 @@ pub fn respond_open_door<T: EventResponse>(
     change_sight_blocking(entity, Los::Clear, world)?;
     change_interact_response(entity, ResponseFuctionName::Close, world)?;
     change_image_state(entity, "open", world)?;
-    explore_room_of_entity(entity, &world, map, resources)?;
     make_noise(50, entity, world, map, resources)?;
 
+    if let Some(stats) = world.borrow_entity_component_mut::<RunStats>(event.source()) {
+        stats.doors_opened += 1;
+    }
+
     let event_data = EventArguments::new(
         response_data.entity, 
         event.source(), 
@@ -117,6 +122,37 @@ pub fn respond_close<T: EventResponse>(
     event.apply(event_data)
 }
 
+// Walking into a secret door searches it, with the odds of searching on purpose.
+pub fn respond_search<T: EventResponse>(
+    event: &dyn Event<Response = T>,
+    response_data: ResponseArguments,
+    args: &HashMap<String, f64>,
+    msg_args: &HashMap<String, String>,
+) -> Result<()> {
+    let ResponseArguments { world, map, resources, entity } = response_data;
+
+    let is_found = match world.borrow_entity_component::<Attributes>(event.source()) {
+        Some(attributes) => game_rng().gen_bool(attributes::search_chance(attributes, true)),
+        None => false,
+    };
+
+    if is_found {
+        reveal_secret_door(entity, world, map)?;
+        logger::log_message("You found a secret door!");
+    }
+
+    let event_data = EventArguments::new(
+        response_data.entity, 
+        event.source(), 
+        args, 
+        msg_args, 
+        world, 
+        map, 
+        resources
+    );
+    event.apply(event_data)
+}
+
 pub fn respond_reveal_room<T: EventResponse>(
     event: &dyn Event<Response = T>,
     response_data: ResponseArguments,
@@ -164,6 +200,32 @@ pub fn respond_pickup<T: EventResponse>(
     Ok(())
 }
 
+pub fn respond_take_artifact<T: EventResponse>(
+    event: &dyn Event<Response = T>,
+    response_data: ResponseArguments,
+    args: &HashMap<String, f64>,
+    msg_args: &HashMap<String, String>,
+) -> Result<()> {
+    let ResponseArguments { world, map, resources, entity } = response_data;
+    world.add_component(event.source(), Artifact)?;
+    logger::log_message("You take the artifact. Now find the exit!");
+
+    let event_data = EventArguments::new(
+        response_data.entity, 
+        event.source(), 
+        args, 
+        msg_args, 
+        world, 
+        map, 
+        resources
+    );
+    event.apply(event_data)?;
+
+    world.remove_entity(entity)?;
+
+    Ok(())
+}
+
 pub fn respond_drop<T: EventResponse>(
     event: &dyn Event<Response = T>,
     response_data: ResponseArguments,
@@ -232,6 +294,18 @@ fn change_collision(entity: usize, new_state: Passable, world: &mut World) -> Re
     }
 }
 
+// Turns a secret door into a regular closed door.
+pub fn reveal_secret_door(entity: usize, world: &mut World, map: &mut GameMap) -> Result<()> {
+    world.remove_component::<Secret>(entity)?;
+    change_interact_response(entity, ResponseFuctionName::OpenDoor, world)?;
+    change_image_state(entity, "closed", world)?;
+
+    if let Some(Position(coordinate)) = world.borrow_entity_component::<Position>(entity) {
+        map.explored.borrow_mut().insert(*coordinate);
+    }
+    Ok(())
+}
+
 fn change_interact_response(
     entity: usize,
     new_response: ResponseFuctionName,
@@ -308,6 +382,10 @@ fn give_coins(own_entity: usize, other_entity: usize, world: &mut World) -> Resu
         xp.current += amount;
     }
 
+    if let Some(stats) = world.borrow_entity_component_mut::<RunStats>(other_entity) {
+        stats.gold_gained += amount;
+    }
+
     logger::log_message(&format!("Found {amount} gold!"));
     Ok(())
 }
@@ -352,7 +430,7 @@ fn drop_coins(own_entity: usize, location: Coordinate, world: &mut World, map: &
     Ok(())
 }
 
-fn make_noise(magnitude: u32, own_entity: usize, world: &mut World, map: &mut GameMap, resources: &ResourceManager) -> Result<()> {
+pub fn make_noise(magnitude: u32, own_entity: usize, world: &mut World, map: &mut GameMap, resources: &ResourceManager) -> Result<()> {
     let event = super::stealth_events::NoiseEvent::new(own_entity, magnitude);
 
     let Some(Position(location)) = world.borrow_entity_component(own_entity) else {
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use crate::{
-    component::{behavior::{Behavior, BehaviorState}, responses::NoiseResponse, Position}, error::Result, map::GameMap, resources::ResourceManager, world::World
+    component::{behavior::{Behavior, BehaviorState}, responses::NoiseResponse, Position}, error::Result, logger, map::{utils::Coordinate, GameMap}, resources::ResourceManager, world::World
 };
 
 use super::{Event, EventArguments};
@@ -23,7 +23,7 @@ impl Event for NoiseEvent {
         &self,
         event_data: EventArguments,
     ) -> Result<()> {
-        try_wake_up(event_data.target, event_data.source, self.magnitude, event_data.world)?;
+        try_wake_up(event_data.target, event_data.source, self.magnitude, event_data.world, event_data.map)?;
         Ok(())
     }
 
@@ -33,14 +33,15 @@ impl Event for NoiseEvent {
 }
 
 
-pub fn try_wake_up(own_entity: usize, source: usize, magnitude: u32, world: &mut World) -> Result<()> {
+// Sleeping monsters hear noise that is still at least as loud as their threshold once it reaches them.
+pub fn try_wake_up(own_entity: usize, source: usize, magnitude: u32, world: &mut World, map: &GameMap) -> Result<()> {
     let Some(Position(own_position)) = world.borrow_entity_component::<Position>(own_entity) else {
         return Err(format!("Entity not found {own_entity}").into());
     };
     let own_position = own_position.clone();
 
     let Some(Position(source_position)) = world.borrow_entity_component::<Position>(source) else {
-        return Err(format!("Entity not found {own_entity}").into());
+        return Err(format!("Entity not found {source}").into());
     };
     let source_position = source_position.clone();
 
@@ -49,23 +50,33 @@ pub fn try_wake_up(own_entity: usize, source: usize, magnitude: u32, world: &mut
     };
     let threshold = threshold.clone();
 
-    let Some(Behavior { state, ..}) = world.borrow_entity_component_mut::<Behavior>(own_entity) else {
+    let distance = own_position.distance(source_position);
+    if magnitude as f64 - distance >= threshold as f64 {
+        alert(own_entity, source_position, world, map)?;
+    }
+
+    Ok(())
+}
+
+// Points the monster at something it noticed. A sleeping monster spends its next action getting up,
+// see BehaviorState::Alerted, while an awake one goes to have a look.
+pub fn alert(own_entity: usize, noticed_at: Coordinate, world: &mut World, map: &GameMap) -> Result<()> {
+    let Some(behavior) = world.borrow_entity_component_mut::<Behavior>(own_entity) else {
         return Err(format!("Tried to wake entity without behavior: {own_entity}").into());
     };
-    *state = BehaviorState::Awake;
-
-    // let distance = own_position.distance(source_position);
-    // if threshold <= magnitude - distance as u32 {
-    //     *state = BehaviorState::Awake;
+    behavior.last_known_position = Some(noticed_at);
+    if behavior.state != BehaviorState::Asleep {
+        return Ok(());
+    }
+    behavior.state = BehaviorState::Alerted;
 
-    //     let name = world.borrow_entity_component(own_entity);
-    //     let msg = logger::generate_wake_up_message(name);
-    //     logger::log_message(&msg);
-    // } else {
-    //     let name = world.borrow_entity_component(own_entity);
-    //     let msg = logger::generate_sleep_message(name);
-    //     logger::log_message(&msg);
-    // }
+    let Some(Position(position)) = world.borrow_entity_component::<Position>(own_entity) else {
+        return Ok(());
+    };
+    if map.is_tile_visible(*position) {
+        let msg = logger::generate_wake_up_message(world.borrow_entity_component(own_entity));
+        logger::log_message(&msg);
+    }
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,12 +1,13 @@
-use log::{debug, warn};
+use log::{debug, info, warn};
 use slint::ModelRc;
 
 use crate::component::attributes::{attack_damage_boost, Attribute};
 use crate::component::spell::{self, SpellEffectArguments, Spellbook};
-use crate::component::tags::StairsDown;
-use crate::component::{health, Position};
+use crate::component::stats::RunStats;
+use crate::component::tags::{Artifact, Exit, StairsDown};
+use crate::component::{health, Destination, Position};
 use crate::error::{Error, Result};
-use crate::ui::SpellbookModel;
+use crate::ui::{RunStatsModel, SpellbookModel};
 use crate::{
     component::{
         attributes::{Attributes, Xp, XpStatus},
@@ -21,16 +22,22 @@ use crate::{
         combat_events::{AttackEvent, ShootEvent},
         interact_events::{InteractEvent, PickupEvent},
     },
+    highscore::{self, HighScoreEntry},
     logger,
+    morgue::{Morgue, RunOutcome},
     map::{
         generation,
-        los::line_of_sight,
+        lighting,
+        los::{cover_on_line, line_of_sight},
         pathfinding::{astar_heuristic_factory, pathfind},
+        boxextends::BoxExtends,
+        special,
         tile::GameTile,
         utils::Coordinate,
+        validation::validate_level,
         GameMap,
     },
-    resources::{id::ImageID, ResourceManager},
+    resources::{id::{BranchID, ImageID}, ResourceManager, MAIN_BRANCH},
     spawning::spawn_all_entities,
     system::{self, Scheduler},
     ui::{MapModel, PlayerModel},
@@ -48,16 +55,27 @@ use crate::system::definitions::*;
 // cast spell (id, position)    check
 // wait/end turn                check
 
-pub const MAP_SIZE_X: u32 = 16 * 2;
-pub const MAP_SIZE_Y: u32 = 9 * 2;
-
 pub const RANGE_EPSILON: f64 = 0.25;
 pub const INTERACT_RANGE: f64 = 1.0 + RANGE_EPSILON;
 
+// How much darker tiles are drawn in dim light, and when they are only remembered.
+const DIM_SHADE: f32 = 0.3;
+const REMEMBERED_SHADE: f32 = 0.5;
+
+// What the player is aiming with when listing or cycling targets.
+#[derive(Debug, Clone, Copy)]
+pub enum TargetMode {
+    Attack(AttackType),
+    Spell(usize),
+}
+
 pub struct Game {
     map: GameMap,
     world: World,
     scheduler: Scheduler,
+    target: Option<usize>,
+    outcome: Option<RunOutcome>,
+    final_score: Option<(HighScoreEntry, usize)>,
 }
 
 impl Game {
@@ -73,25 +91,45 @@ impl Game {
                 attempts += 1;
             }
 
-            (map, bsp) = generation::generate_new(MAP_SIZE_X, MAP_SIZE_Y, 1, &resources);
+            if let Some(level) = resources.get_special_level(1) {
+                (map, world) = special::create_special_level(level, resources)?;
+                scheduler = Scheduler::new();
+                explore_player_room(&mut map, &world, resources)?;
+                special::enter_special_level(level, &mut world);
+                break;
+            }
+
+            (map, bsp) = generation::generate_new(1, MAIN_BRANCH, &resources);
             world = World::new_with(bsp);
             scheduler = Scheduler::new();
 
             let result = spawn_all_entities(&map, &mut world, resources);
-            if let Err(_) = result {
+            if let Err(error) = result {
+                warn!("Generating the level again because spawning failed: {error}");
                 continue;
             }
 
-            let result = explore_player_room(&mut map, &world);
+            let result = explore_player_room(&mut map, &world, resources);
             if let Err(_) = result {
                 continue;
             }
+
+            let issues = validate_level(&map, &world, resources);
+            if !issues.is_empty() {
+                warn!("Generating the level again because of {issues:?}");
+                continue;
+            }
             break;
         }
 
+        scheduler.add_system(terrain_effects, system::TimeSlot::EndOfTurn);
         scheduler.add_system(reap_units, system::TimeSlot::EndOfTurn);
+        scheduler.add_system(special_level_exits, system::TimeSlot::EndOfTurn);
         scheduler.add_system(level_up_check, system::TimeSlot::EndOfTurn);
+        scheduler.add_system(monster_perception, system::TimeSlot::EndOfTurn);
         scheduler.add_system(monster_turns, system::TimeSlot::EndOfTurn);
+        scheduler.add_system(track_floor_progress, system::TimeSlot::EndOfTurn);
+        scheduler.add_system(passive_search, system::TimeSlot::EndOfTurn);
 
         scheduler.add_system(spell_cooldowns, system::TimeSlot::EndOfLevel);
 
@@ -99,6 +137,9 @@ impl Game {
             map,
             world,
             scheduler,
+            target: None,
+            outcome: None,
+            final_score: None,
         })
     }
 
@@ -173,6 +214,17 @@ impl Game {
         model
     }
 
+    pub fn get_run_stats(&self) -> RunStatsModel {
+        let Ok(player) = self.world.get_player_id() else {
+            return RunStatsModel::default();
+        };
+
+        self.world
+            .borrow_entity_component::<RunStats>(player)
+            .map(RunStatsModel::from)
+            .unwrap_or_default()
+    }
+
     pub fn get_spell_info(&self) -> SpellbookModel {
         let mut model = SpellbookModel::default();
         let Ok(player) = self.world.get_player_id() else {
@@ -200,50 +252,126 @@ impl Game {
         model
     }
 
-    pub fn get_sprite_ids(&self, resources: &ResourceManager) -> Vec<Vec<i32>> {
-        let max_tile_index = self.map.height * self.map.width;
-
+    // Images for every tile inside the view, row by row, and how much darker the tile is drawn.
+    // Visible tiles show everything on them, remembered ones how they looked when last seen, without monsters.
+    // Tiles outside the map show as unexplored.
+    pub fn get_sprite_ids_in(&self, view: BoxExtends, resources: &ResourceManager) -> Vec<(Vec<i32>, f32)> {
         let mut tile_images = Vec::new();
 
-        for index in 0..max_tile_index {
-            let mut images = Vec::new();
-
-            if self.map.is_tile_explored_from_index(index) {
-                let tile_id = self.map.get_game_tile_from(index);
-                let tile = resources.get_tile(tile_id);
-                let ImageID(image) = tile.unwrap_or(&GameTile::default()).image;
-                images.push(image as i32);
+        for y in view.top_left.y..=view.bottom_right.y {
+            for x in view.top_left.x..=view.bottom_right.x {
+                let coordinate = Coordinate { x, y };
+
+                let tile = if self.map.is_tile_visible(coordinate) {
+                    let mut images = vec![self.get_tile_image(coordinate, resources)];
+                    images.extend(self.get_images_at(coordinate, true));
+                    let shade = match self.map.get_light_level(coordinate) < lighting::BRIGHT_LIGHT {
+                        true => DIM_SHADE,
+                        false => 0.0,
+                    };
+                    (images, shade)
+                } else if self.map.is_tile_explored(coordinate) {
+                    // tiles revealed without being seen, like by scrying, have no memory yet
+                    let images = match self.map.get_remembered_images(coordinate) {
+                        Some(images) => images.into_iter().map(|ImageID(image)| image as i32).collect(),
+                        None => {
+                            let mut images = vec![self.get_tile_image(coordinate, resources)];
+                            images.extend(self.get_images_at(coordinate, false));
+                            images
+                        }
+                    };
+                    (images, REMEMBERED_SHADE)
+                } else {
+                    (vec![crate::resources::DEFAULT_IMAGE_ID.0 as i32], 0.0)
+                };
 
-                let entity_images = self.get_images_at_index(index);
-                images.extend(entity_images);
-            } else {
-                images.push(crate::resources::DEFAULT_IMAGE_ID.0 as i32);
+                tile_images.push(tile);
             }
-
-            tile_images.push(images);
         }
         tile_images
     }
 
-    fn get_images_at_index(&self, index: u32) -> impl Iterator<Item = i32> + use<'_> {
-        let coordinate = Coordinate {
-            x: (index % self.map.width) as i32,
-            y: (index / self.map.width) as i32,
-        };
+    fn get_tile_image(&self, coordinate: Coordinate, resources: &ResourceManager) -> i32 {
+        let tile_id = self.map.get_game_tile(coordinate);
+        let tile = resources.get_tile(tile_id);
+        let ImageID(image) = tile.unwrap_or(&GameTile::default()).image;
+        image as i32
+    }
+
+    fn get_images_at(&self, coordinate: Coordinate, with_monsters: bool) -> impl Iterator<Item = i32> + use<'_> {
         self.world
             .get_entities_at_coordinate(coordinate)
             .into_iter()
+            .filter(move |entity| with_monsters || self.world.borrow_entity_component::<Monster>(*entity).is_none())
             .filter_map(|entity| self.get_entity_image(entity))
             .map(|image_id| image_id.0 as i32)
         //sort by depth later
     }
 
+    pub fn get_player_position(&self) -> Option<Coordinate> {
+        self.world.get_player_position().ok()
+    }
+
     fn get_entity_image(&self, entity: usize) -> Option<ImageID> {
         self.world
             .borrow_entity_component::<ImageID>(entity)
             .copied()
     }
 
+    // Hostile entities the player can see and reach with the given attack or spell, nearest first.
+    pub fn get_visible_targets(
+        &self,
+        mode: TargetMode,
+        resources: &ResourceManager,
+    ) -> Vec<(usize, Coordinate)> {
+        let Ok(origin) = self.world.get_player_position() else {
+            return vec![];
+        };
+        let Some(range) = self.get_target_mode_range(mode) else {
+            return vec![];
+        };
+        let Some(monsters) = self.world.borrow_component_vec::<Monster>() else {
+            return vec![];
+        };
+
+        let mut targets: Vec<(usize, Coordinate)> = monsters
+            .iter()
+            .enumerate()
+            .filter_map(|(entity, tag)| tag.as_ref().map(|_| entity))
+            .filter_map(|entity| {
+                let Position(position) = self.world.borrow_entity_component::<Position>(entity)?;
+                Some((entity, *position))
+            })
+            .filter(|(_, position)| {
+                origin.distance(*position) <= range
+                    && self.map.is_tile_visible(*position)
+                    && line_of_sight(origin, *position, &self.map, &self.world, resources)
+            })
+            .collect();
+
+        targets.sort_by(|(_, a), (_, b)| origin.distance(*a).total_cmp(&origin.distance(*b)));
+        targets
+    }
+
+    fn get_target_mode_range(&self, mode: TargetMode) -> Option<f64> {
+        let player = self.world.get_player_id().ok()?;
+
+        match mode {
+            TargetMode::Attack(attack_type) => {
+                let combat = self.world.borrow_entity_component::<Combat>(player)?;
+                let attack = match attack_type {
+                    AttackType::Melee => &combat.melee_attack,
+                    AttackType::Ranged => &combat.ranged_attack,
+                };
+                attack.as_ref().map(|attack| attack.range)
+            }
+            TargetMode::Spell(index) => {
+                let spellbook = self.world.borrow_entity_component::<Spellbook>(player)?;
+                spellbook.get_spell(index)?.effect.range()
+            }
+        }
+    }
+
     pub fn player_health_status(&self) -> HealthStatus {
         let Ok(player) = self.world.get_player_id() else {
             return HealthStatus::Full;
@@ -315,7 +443,11 @@ impl Game {
             return Err("Player has no spellbook".into());
         };
         spellbook.register_cast(index)?;
-        
+
+        if let Some(stats) = self.world.borrow_entity_component_mut::<RunStats>(player) {
+            stats.register_cast(&spell.name.0);
+        }
+
         self.end_turn(resources)
     }
 
@@ -362,7 +494,8 @@ impl Game {
             return Err(Error::NoPlayerFound);
         };
 
-        if !self.map.is_tile_walkable(destination, resources) {
+        let movement = self.world.get_movement(self.world.get_player_id()?);
+        if !self.map.is_tile_passable(destination, movement, resources) {
             logger::log_message("Destination tile is not walkable.");
             return Ok(());
         };
@@ -391,6 +524,7 @@ impl Game {
             &self.world,
             resources,
             heuristic,
+            movement,
             false,
             false,
             std::u32::MAX,
@@ -410,8 +544,11 @@ impl Game {
             };
 
             let step = origin + step_direction;
-            self.move_player(step, resources)?;
-            self.end_turn(resources)?;
+            if self.map.is_tile_hazardous(step, movement, resources) {
+                logger::log_message("Auto travel stopped before dangerous ground.");
+                return Ok(());
+            }
+            self.step_player(step, resources)?;
         }
 
         logger::log_message("Arrived at destination.");
@@ -462,27 +599,79 @@ impl Game {
         Ok(())
     }
 
+    pub fn next_target_command(&mut self, mode: TargetMode, resources: &ResourceManager) -> Result<()> {
+        let targets = self.get_visible_targets(mode, resources);
+        if targets.is_empty() {
+            self.target = None;
+            logger::log_message("No targets in sight.");
+            return Ok(());
+        }
+
+        let current_index = self
+            .target
+            .and_then(|current| targets.iter().position(|(entity, _)| *entity == current));
+
+        let next_index = match current_index {
+            Some(index) => (index + 1) % targets.len(),
+            None => 0,
+        };
+
+        let (entity, position) = targets[next_index];
+        self.target = Some(entity);
+
+        let Ok(origin) = self.world.get_player_position() else {
+            return Err(Error::NoPlayerFound);
+        };
+        let cover = cover_on_line(origin, position, &self.map, &self.world, resources);
+        let name = self.world.borrow_entity_component::<Name>(entity);
+        logger::log_message(&logger::generate_target_message(name, cover));
+        Ok(())
+    }
+
+    // Fires at the selected target if it is still valid, otherwise at the nearest one.
+    pub fn fire_at_target_command(&mut self, mode: TargetMode, resources: &ResourceManager) -> Result<()> {
+        let targets = self.get_visible_targets(mode, resources);
+
+        let selected = self
+            .target
+            .and_then(|current| targets.iter().find(|(entity, _)| *entity == current));
+
+        let Some((entity, position)) = selected.or(targets.first()).copied() else {
+            logger::log_message("No targets in sight.");
+            return Ok(());
+        };
+        self.target = Some(entity);
+
+        match mode {
+            TargetMode::Attack(AttackType::Melee) => {
+                let result = self.perform_attack(position, AttackType::Melee, resources, false);
+                match result {
+                    Err(Error::InvalidTarget) => return Ok(()),
+                    Err(error) => debug!("{error}"),
+                    Ok(_) => {},
+                }
+                self.end_turn(resources)
+            }
+            TargetMode::Attack(AttackType::Ranged) => self.shoot_command(position, resources, false),
+            TargetMode::Spell(index) => self.cast_spell_command(index, position, resources),
+        }
+    }
+
     pub fn descend_command(&mut self, resources: &ResourceManager) -> Result<()> {
         let Ok(player_location) = self.world.get_player_position() else {
             return Err(Error::InvalidTarget);
         };
 
-        let location_has_stairs = self
-            .world
-            .get_entities_at_coordinate(player_location)
-            .into_iter()
-            .find(|entity| {
-                self.world
-                    .borrow_entity_component::<StairsDown>(*entity)
-                    .is_some()
-            })
-            .is_some();
+        if self.location_has::<Exit>(player_location) {
+            return self.leave_dungeon(resources);
+        }
 
-        if !location_has_stairs {
+        if !self.location_has::<StairsDown>(player_location) {
             return Err(Error::InvalidTarget);
         }
 
-        let new_depth = self.map.depth + 1;
+        let destination = self.stairs_destination(player_location, resources);
+        let new_depth = destination.depth;
 
         let mut new_world;
         let mut new_map;
@@ -495,11 +684,25 @@ impl Game {
                 attempts += 1;
             }
 
-            (new_map, new_bsp) = generation::generate_new(MAP_SIZE_X, MAP_SIZE_Y, new_depth, resources);
+            // handcrafted levels replace generation at their depth on the main line
+            let special_level = match destination.branch {
+                MAIN_BRANCH => resources.get_special_level(new_depth),
+                _ => None,
+            };
+            if let Some(level) = special_level {
+                (new_map, new_world) = special::create_special_level(level, resources)?;
+                new_world.import_player(&self.world)?;
+                explore_player_room(&mut new_map, &new_world, resources)?;
+                special::enter_special_level(level, &mut new_world);
+                break;
+            }
+
+            (new_map, new_bsp) = generation::generate_new(new_depth, destination.branch, resources);
             new_world = World::new_with(new_bsp);
 
             let result = spawn_all_entities(&new_map, &mut new_world, resources);
-            if let Err(_) = result {
+            if let Err(error) = result {
+                warn!("Generating the level again because spawning failed: {error}");
                 continue;
             };
 
@@ -508,34 +711,93 @@ impl Game {
                 continue;
             };
 
-            let result = explore_player_room(&mut new_map, &new_world);
+            let result = explore_player_room(&mut new_map, &new_world, resources);
             if let Err(_) = result {
                 continue;
             };
 
+            let issues = validate_level(&new_map, &new_world, resources);
+            if !issues.is_empty() {
+                warn!("Generating the level again because of {issues:?}");
+                continue;
+            }
+
             break;
         }
 
+        let previous_branch = self.map.branch;
         self.map = new_map;
         self.world = new_world;
+        self.target = None;
 
         self.scheduler.on_descend_floor(&mut self.world, &mut self.map, resources)?;
 
+        announce_branches(previous_branch, &self.map, &self.world, resources);
+
+        if resources.is_final_depth(self.map.depth) {
+            logger::log_message("The artifact is somewhere on this floor. There are no stairs further down.");
+        }
+
         Ok(())
     }
 
+    // Leaving through the exit ends the run, but only with the artifact in hand.
+    fn leave_dungeon(&mut self, resources: &ResourceManager) -> Result<()> {
+        let player = self.world.get_player_id()?;
+
+        if self.world.borrow_entity_component::<Artifact>(player).is_none() {
+            logger::log_message("You cannot leave without the artifact.");
+            return Ok(());
+        }
+
+        logger::log_message("You escape the dungeon with the artifact!");
+        self.end_run(RunOutcome::Victory, resources);
+        Ok(())
+    }
+
+    // Stairs without a destination, like the ones opened on special levels, lead one floor further down.
+    fn stairs_destination(&self, location: Coordinate, resources: &ResourceManager) -> Destination {
+        self.world
+            .get_entities_at_coordinate(location)
+            .into_iter()
+            .filter(|entity| self.world.borrow_entity_component::<StairsDown>(*entity).is_some())
+            .find_map(|entity| self.world.borrow_entity_component::<Destination>(entity).map(|destination| *destination))
+            .unwrap_or_else(|| self.map.next_destination(resources))
+    }
+
+    fn location_has<T: 'static>(&self, location: Coordinate) -> bool {
+        self.world
+            .get_entities_at_coordinate(location)
+            .into_iter()
+            .any(|entity| self.world.borrow_entity_component::<T>(entity).is_some())
+    }
+
+    pub fn search_command(&mut self, resources: &ResourceManager) -> Result<()> {
+        let Ok(player) = self.world.get_player_id() else {
+            return Err(Error::NoPlayerFound);
+        };
+
+        let found = search_for_secrets(player, ACTIVE_SEARCH_RADIUS, true, &mut self.world, &mut self.map)?;
+        match found {
+            0 => logger::log_message("You search the walls but find nothing."),
+            _ => logger::log_message("You found a secret door!"),
+        }
+
+        self.end_turn(resources)
+    }
+
+    // Waiting on stairs takes them. The exit is only left on purpose, through descend_command.
     pub fn wait_command(&mut self, resources: &ResourceManager) -> Result<()> {
-        let result = self.descend_command(resources);
-        match  result {
-            Ok(_) => Ok(()),
-            Err(Error::InvalidTarget) => {
-                logger::log_message("Waited for a turn.");
-                self.end_turn(resources)
-            }  
-            Err(error) => {
-                Err(error)
-            }
+        let on_stairs = self
+            .world
+            .get_player_position()
+            .is_ok_and(|position| self.location_has::<StairsDown>(position));
+        if on_stairs {
+            return self.descend_command(resources);
         }
+
+        logger::log_message("Waited for a turn.");
+        self.end_turn(resources)
     }
 
     fn end_turn(&mut self, resources: &ResourceManager) -> Result<()> {
@@ -546,9 +808,65 @@ impl Game {
 
         self.scheduler
             .on_end_turn(&mut self.world, &mut self.map, resources)?;
+
+        // monsters may have opened doors or the player may have moved
+        if let Ok(player_position) = self.world.get_player_position() {
+            self.map.update_field_of_view(player_position, &self.world, resources);
+        }
+
+        if !self.is_run_over() && self.player_health_status() == HealthStatus::Dead {
+            self.end_run(RunOutcome::Died, resources);
+        }
         Ok(())
     }
 
+    pub fn is_run_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn get_run_outcome(&self) -> Option<RunOutcome> {
+        self.outcome
+    }
+
+    // The finished run's high score entry and its rank in the table.
+    pub fn get_final_score(&self) -> Option<&(HighScoreEntry, usize)> {
+        self.final_score.as_ref()
+    }
+
+    pub fn get_high_scores(amount: usize) -> Result<Vec<HighScoreEntry>> {
+        highscore::top_high_scores(amount)
+    }
+
+    // Records a run that is given up on before it ended, so restarting still leaves a morgue file and a score.
+    pub fn abandon_run(&mut self, resources: &ResourceManager) {
+        if !self.is_run_over() {
+            self.end_run(RunOutcome::Abandoned, resources);
+        }
+    }
+
+    fn end_run(&mut self, outcome: RunOutcome, resources: &ResourceManager) {
+        self.outcome = Some(outcome);
+
+        let morgue = match Morgue::new(outcome, &self.map, &self.world, resources) {
+            Ok(morgue) => morgue,
+            Err(error) => {
+                warn!("Failed to create morgue: {error}");
+                return;
+            }
+        };
+
+        match morgue.save() {
+            Ok(path) => info!("Wrote morgue file to {}", path.display()),
+            Err(error) => warn!("Failed to write morgue file: {error}"),
+        }
+
+        let entry = HighScoreEntry::from(&morgue);
+        match highscore::record_high_score(entry.clone()) {
+            Ok(rank) => self.final_score = Some((entry, rank)),
+            Err(error) => warn!("Failed to record high score: {error}"),
+        }
+    }
+
     pub fn level_up_command(&mut self, stat: Attribute, amount: u32) -> Result<()> {
         let Ok(player) = self.world.get_player_id() else {
             Err(Error::NoPlayerFound)?
@@ -593,12 +911,29 @@ impl Game {
         destination: Coordinate,
         resources: &ResourceManager,
     ) -> Result<()> {
-        let true = self.map.is_tile_walkable(destination, resources) else {
+        let movement = self.world.get_movement(self.world.get_player_id()?);
+        let true = self.map.is_tile_passable(destination, movement, resources) else {
             logger::log_message("Can't walk there.");
             return Ok(());
         };
+        self.step_player(destination, resources)?;
+        Ok(())
+    }
+
+    // Moves the player one tile and passes as many turns as the tile costs to enter.
+    fn step_player(&mut self, destination: Coordinate, resources: &ResourceManager) -> Result<()> {
+        let player = self.world.get_player_id()?;
+        let movement = self.world.get_movement(player);
+        let cost = self.map.get_movement_cost(destination, movement, resources);
+
         self.move_player(destination, resources)?;
-        self.end_turn(resources)?;
+        enter_tile(player, &mut self.world, &mut self.map, resources)?;
+        for _ in 0..cost {
+            self.end_turn(resources)?;
+            if self.is_run_over() {
+                break;
+            }
+        }
         Ok(())
     }
 
@@ -607,6 +942,7 @@ impl Game {
             return Err(Error::NoPlayerFound);
         };
         self.world.update_position(player, destination);
+        self.map.update_field_of_view(destination, &self.world, resources);
         self.broadcast_pickup(destination, resources)?;
         Ok(())
     }
@@ -738,10 +1074,11 @@ impl Game {
                     )?;
                 }
                 AttackType::Ranged => {
+                    let cover = cover_on_line(origin, target, &self.map, &self.world, resources);
                     self.world.send_event(
                         &mut self.map,
                         resources,
-                        &ShootEvent::new(player),
+                        &ShootEvent::new(player).with_cover(cover),
                         entity,
                     )?;
                 }
@@ -755,12 +1092,35 @@ impl Game {
     }
 }
 
-fn explore_player_room(map: &mut GameMap, world: &World) -> Result<()> {
+// Tells the player when they enter or leave a branch, and when a staircase on the new floor leads into one.
+fn announce_branches(previous_branch: BranchID, map: &GameMap, world: &World, resources: &ResourceManager) {
+    if map.branch != previous_branch {
+        match resources.get_branch(map.branch) {
+            Some(branch) => logger::log_message(&format!("You enter the {}.", branch.name)),
+            None => logger::log_message("You are back on the main path through the dungeon."),
+        }
+    }
+
+    let Some(destinations) = world.borrow_component_vec::<Destination>() else {
+        return;
+    };
+    let side_paths: Vec<BranchID> = destinations
+        .iter()
+        .flatten()
+        .map(|destination| destination.branch)
+        .filter(|branch| *branch != MAIN_BRANCH && *branch != map.branch)
+        .collect();
+    for branch in side_paths.into_iter().filter_map(|branch| resources.get_branch(branch)) {
+        logger::log_message(&format!("A second staircase on this floor leads into the {}.", branch.name));
+    }
+}
+
+fn explore_player_room(map: &mut GameMap, world: &World, resources: &ResourceManager) -> Result<()> {
     let Ok(position) = world.get_player_position() else {
         warn!("can't explore player room");
         return Err(Error::NoPlayerFound);
     };
 
-    map.explore_room(position);
+    map.update_field_of_view(position, world, resources);
     Ok(())
 }
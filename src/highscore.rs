@@ -0,0 +1,125 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Result, morgue::{Morgue, RunOutcome}};
+
+const HIGHSCORE_PATH: &'static str = "highscores.yaml";
+
+const SCORE_PER_DEPTH: u32 = 250;
+const SCORE_PER_XP: u32 = 1;
+// Picking up gold grants as much xp, so gold on its own only adds a point per this many coins.
+const GOLD_PER_SCORE: u32 = 4;
+const SCORE_FOR_VICTORY: u32 = 2500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: u32,
+    pub depth: u32,
+    pub level: u32,
+    pub gold: u32,
+    pub cause: String,
+    pub date: String,
+}
+
+impl From<&Morgue> for HighScoreEntry {
+    fn from(value: &Morgue) -> Self {
+        Self {
+            name: value.name.clone(),
+            score: calculate_score(value.outcome, value.depth, value.gold, value.xp),
+            depth: value.depth,
+            level: value.level,
+            gold: value.gold,
+            cause: value.cause.clone(),
+            date: current_date(),
+        }
+    }
+}
+
+impl HighScoreEntry {
+    pub fn to_line(&self) -> String {
+        format!(
+            "{:>7}  {:<12} level {:<3} depth {:<3} {:>5} gold  {} ({})",
+            self.score, self.name, self.level, self.depth, self.gold, self.cause, self.date
+        )
+    }
+}
+
+pub fn calculate_score(outcome: RunOutcome, depth: u32, gold: u32, xp: u32) -> u32 {
+    let bonus = match outcome {
+        RunOutcome::Died | RunOutcome::Abandoned => 0,
+        RunOutcome::Victory => SCORE_FOR_VICTORY,
+    };
+    depth * SCORE_PER_DEPTH + gold / GOLD_PER_SCORE + xp * SCORE_PER_XP + bonus
+}
+
+// Adds the entry to the table on disk and returns its rank, starting at 1.
+pub fn record_high_score(entry: HighScoreEntry) -> Result<usize> {
+    let path = Path::new(HIGHSCORE_PATH);
+    let mut entries = load_from_yaml(path)?;
+
+    let rank = entries
+        .iter()
+        .position(|other| other.score < entry.score)
+        .unwrap_or(entries.len());
+    entries.insert(rank, entry);
+
+    save_to_yaml(&entries, path)?;
+    Ok(rank + 1)
+}
+
+pub fn top_high_scores(amount: usize) -> Result<Vec<HighScoreEntry>> {
+    let mut entries = load_from_yaml(Path::new(HIGHSCORE_PATH))?;
+    entries.truncate(amount);
+    Ok(entries)
+}
+
+pub fn save_to_yaml(entries: &Vec<HighScoreEntry>, path: &Path) -> Result<()> {
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    serde_yaml::to_writer(writer, entries)?;
+
+    Ok(())
+}
+
+// A missing file is an empty table, not an error.
+pub fn load_from_yaml(path: &Path) -> Result<Vec<HighScoreEntry>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let mut entries: Vec<HighScoreEntry> = serde_yaml::from_reader(reader)?;
+    entries.sort_by(|a, b| b.score.cmp(&a.score));
+
+    Ok(entries)
+}
+
+fn current_date() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    // Converts days since epoch to a civil date, see Howard Hinnant's date algorithms.
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
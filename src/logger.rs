@@ -2,6 +2,7 @@ use std::cell::RefCell;
 use std::collections::VecDeque;
 
 use crate::component::Name;
+use crate::map::los::Cover;
 
 thread_local!(
     pub static LOG: MessageLog = MessageLog::new();
@@ -27,19 +28,40 @@ The templates refering to burning or fire are deprecated.
  */ 
 
 
+const HISTORY_SIZE: usize = 100;
+
 pub struct MessageLog {
     message_queue: RefCell<VecDeque<String>>,
+    history: RefCell<VecDeque<String>>,
 }
 
 impl MessageLog {
     pub fn new() -> Self {
         MessageLog {
             message_queue: RefCell::new(VecDeque::new()),
+            history: RefCell::new(VecDeque::new()),
         }
     }
 
     pub fn queue_message(&self, msg: &str) {
         self.message_queue.borrow_mut().push_back(msg.to_string());
+
+        let mut history = self.history.borrow_mut();
+        history.push_back(msg.to_string());
+        if history.len() > HISTORY_SIZE {
+            history.pop_front();
+        }
+    }
+
+    // Messages stay in the history after the UI has consumed them, oldest first.
+    pub fn recent_messages(&self, amount: usize) -> Vec<String> {
+        let history = self.history.borrow();
+        let skip = history.len().saturating_sub(amount);
+        history.iter().skip(skip).cloned().collect()
+    }
+
+    pub fn clear_history(&self) {
+        self.history.borrow_mut().clear();
     }
 
     pub fn next_message(&self) -> Option<String> {
@@ -51,6 +73,14 @@ pub fn log_message(msg: &str) {
     LOG.with(|log| log.queue_message(msg));
 }
 
+pub fn recent_messages(amount: usize) -> Vec<String> {
+    LOG.with(|log| log.recent_messages(amount))
+}
+
+pub fn clear_history() {
+    LOG.with(|log| log.clear_history());
+}
+
 pub fn generate_attack_message(
     attacker: Option<&Name>,
     defender: Option<&Name>,
@@ -135,3 +165,16 @@ pub fn generate_sleep_message(name: Option<&Name>) -> String {
     
     vec![&name, "doesn't notice you."].join(" ")
 }
+
+
+pub fn generate_target_message(name: Option<&Name>, cover: Cover) -> String {
+    let name = match name {
+        Some(Name(name)) => name,
+        None => "Someone",
+    };
+
+    match cover.describe() {
+        Some(cover) => vec!["Now targeting", &name, "behind", cover].join(" "),
+        None => vec!["Now targeting", &name].join(" "),
+    }
+}
\ No newline at end of file
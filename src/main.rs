@@ -1,4 +1,4 @@
-use std::{fs::File, rc::Rc};
+use std::{fs::File, path::PathBuf, rc::Rc};
 use game::Game;
 use simplelog::*;
 
@@ -8,9 +8,12 @@ pub mod component;
 pub mod error;
 pub mod event;
 pub mod game;
+pub mod highscore;
 pub mod logger;
 pub mod map;
+pub mod morgue;
 pub mod resources;
+pub mod rng;
 pub mod spawning;
 pub mod system;
 pub mod ui;
@@ -18,6 +21,10 @@ pub mod world;
 
 use crate::error::Result;
 
+const DEFAULT_HIGHSCORE_COUNT: usize = 10;
+const DEFAULT_EXPORT_DIRECTORY: &str = "export";
+const DEFAULT_BATCH_SIZE: u32 = 100;
+
 fn main() -> Result<()> {
     // CombinedLogger::init(vec![
     //     TermLogger::new(
@@ -34,6 +41,57 @@ fn main() -> Result<()> {
     // ])
     // .unwrap();
 
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--highscores") {
+        let amount = args
+            .get(index + 1)
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or(DEFAULT_HIGHSCORE_COUNT);
+
+        for (rank, entry) in Game::get_high_scores(amount)?.iter().enumerate() {
+            println!("{:>3}. {}", rank + 1, entry.to_line());
+        }
+        return Ok(());
+    }
+
+    // generates batches of levels and reports on them: --generation-stats [count] [depths like 1-3 or 1,4] [seed]
+    if let Some(index) = args.iter().position(|arg| arg == "--generation-stats") {
+        let resources = resources::ResourceManager::new()?;
+        let count = args
+            .get(index + 1)
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or(DEFAULT_BATCH_SIZE);
+        let depths = match args.get(index + 2) {
+            Some(arg) => parse_depths(arg)?,
+            None => (1..=resources.get_final_depth()).collect(),
+        };
+        let seed = args
+            .get(index + 3)
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or_else(rand::random);
+
+        println!("Generating {count} levels per depth with seed {seed}");
+        for depth in depths {
+            let statistics = map::statistics::collect_statistics(depth, count, seed, &resources);
+            print!("{}", statistics.report());
+        }
+        return Ok(());
+    }
+
+    // writes the graphs of a freshly generated level as DOT and JSON, to debug generation
+    if let Some(index) = args.iter().position(|arg| arg == "--export-level") {
+        let depth = args
+            .get(index + 1)
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or(1);
+        let directory = PathBuf::from(args.get(index + 2).map_or(DEFAULT_EXPORT_DIRECTORY, |arg| arg.as_str()));
+
+        let resources = resources::ResourceManager::new()?;
+        map::export::export_generated_level(depth, &directory, &resources)?;
+        println!("Wrote the graphs of a depth {depth} level to {}", directory.display());
+        return Ok(());
+    }
+
     let resources = Rc::new(resources::ResourceManager::new()?);
     let game = Game::new(&resources)?;
     let main_window = ui::create_window(game, resources);
@@ -41,3 +99,19 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+// Accepts a single depth, a range like 1-3 or a list like 1,4.
+fn parse_depths(arg: &str) -> Result<Vec<u32>> {
+    let mut depths = vec![];
+    for part in arg.split(',') {
+        let parsed: core::result::Result<Vec<u32>, _> = match part.split_once('-') {
+            Some((low, high)) => low.parse::<u32>().and_then(|low| high.parse().map(|high| (low..=high).collect())),
+            None => part.parse().map(|depth| vec![depth]),
+        };
+        match parsed {
+            Ok(parsed) => depths.extend(parsed),
+            Err(_) => return Err(format!("Invalid depth: {part}").into()),
+        }
+    }
+    Ok(depths)
+}
@@ -1,14 +1,23 @@
 pub mod boxextends;
+pub mod caves;
+pub mod export;
+pub mod fov;
 pub mod generation;
+pub mod lighting;
 pub mod los;
 pub mod pathfinding;
 pub mod room;
+pub mod shape;
+pub mod special;
+pub mod statistics;
+pub mod theme;
 pub mod tile;
 pub mod utils;
+pub mod validation;
 
 use std::{
     cell::RefCell,
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
 };
 
 use itertools::Itertools;
@@ -17,46 +26,79 @@ use utils::flood_fill_explore;
 
 
 use crate::{
-    resources::{id::TileID, ResourceManager},
+    component::{
+        combat::DamageType,
+        health::Health,
+        stats::{self, RunStats},
+        tags::Monster,
+        Destination, Movement, Name,
+    },
+    logger,
+    resources::{id::{BranchID, ImageID, ThemeID, TileID}, ResourceManager, FLOOR_TILEID, MAIN_BRANCH},
     world::World,
 };
 
 use {
+    boxextends::BoxExtends,
     pathfinding::calculate_pathing_grid,
     room::Room,
-    tile::{Los, Passable},
-    utils::Coordinate,
+    tile::{GameTile, Los, Passable},
+    utils::{Coordinate, DOWN, LEFT, RIGHT, UP},
 };
 
 const DEFAULT_HEIGHT: u32 = 32;
 const DEFAULT_WIDTH: u32 = 32;
+// Units caught by a collapsing or flooding tile get hurt and look this far for solid ground.
+const COLLAPSE_DAMAGE: u32 = 4;
+const STRANDED_REACH: i32 = 2;
 
 #[derive(Clone)]
 pub struct GameMap {
     pub map: HashMap<Coordinate, TileID>,
     pub explored: RefCell<HashSet<Coordinate>>,
+    // Tiles the player sees right now, recomputed every turn.
+    pub visible: RefCell<HashSet<Coordinate>>,
+    // Images of every seen tile as they looked when last seen, without monsters.
+    pub remembered: RefCell<HashMap<Coordinate, Vec<ImageID>>>,
+    // Light level of every tile, recomputed together with the field of view.
+    pub light: HashMap<Coordinate, u32>,
     pub room_graph: Graph<Room, (), petgraph::Undirected>,
     pub width: u32,
     pub height: u32,
     pub depth: u32,
-    pub pathing_grid: HashMap<Coordinate, Coordinate>,
+    // Steps towards the player, one grid for every way monsters on the floor can move.
+    pub pathing_grids: HashMap<Movement, HashMap<Coordinate, Coordinate>>,
+    // Damage taken so far by tiles that have health.
+    pub tile_damage: HashMap<Coordinate, u32>,
+    pub theme: Option<ThemeID>,
+    pub branch: BranchID,
 }
 
 impl GameMap {
     pub fn new(width: u32, height: u32) -> Self {
         let map = HashMap::<Coordinate, TileID>::new();
         let explored = RefCell::new(HashSet::<Coordinate>::new());
+        let visible = RefCell::new(HashSet::<Coordinate>::new());
+        let remembered = RefCell::new(HashMap::<Coordinate, Vec<ImageID>>::new());
+        let light = HashMap::new();
         let room_graph = Graph::default();
-        let pathing_grid = HashMap::new();
+        let pathing_grids = HashMap::new();
+        let tile_damage = HashMap::new();
 
         Self {
             map,
             explored,
+            visible,
+            remembered,
+            light,
             width,
             height,
             depth: 0,
             room_graph,
-            pathing_grid,
+            pathing_grids,
+            tile_damage,
+            theme: None,
+            branch: MAIN_BRANCH,
         }
     }
 
@@ -72,6 +114,58 @@ impl GameMap {
         tile.passable == Passable::Walk
     }
 
+    pub fn is_tile_passable(&self, coordinate: Coordinate, movement: Movement, resources: &ResourceManager) -> bool {
+        let Some(tile_id) = self.map.get(&coordinate) else {
+            return false;
+        };
+
+        let Some(tile) = resources.get_tile(*tile_id) else {
+            return false;
+        };
+
+        movement.can_cross(tile.passable)
+    }
+
+    pub fn get_tile_at<'a>(&self, coordinate: Coordinate, resources: &'a ResourceManager) -> Option<&'a GameTile> {
+        let tile_id = self.map.get(&coordinate)?;
+        resources.get_tile(*tile_id)
+    }
+
+    // Anything flying passes over the ground, so it neither slows down nor gets hurt.
+    pub fn get_movement_cost(&self, coordinate: Coordinate, movement: Movement, resources: &ResourceManager) -> u32 {
+        match self.get_tile_at(coordinate, resources) {
+            Some(tile) if !movement.fly => tile.movement_cost.max(1),
+            _ => 1,
+        }
+    }
+
+    pub fn is_tile_hazardous(&self, coordinate: Coordinate, movement: Movement, resources: &ResourceManager) -> bool {
+        match self.get_tile_at(coordinate, resources) {
+            Some(tile) => !movement.fly && tile.is_hazardous(),
+            None => false,
+        }
+    }
+
+    // Depth the spawns of this floor are picked and scaled for, branches can be harder than their depth.
+    pub fn spawn_depth(&self, resources: &ResourceManager) -> u32 {
+        let offset = resources.get_branch(self.branch).map_or(0, |branch| branch.depth_offset);
+        self.depth + offset
+    }
+
+    // Where stairs on this floor lead unless they say otherwise.
+    // The last floor of a branch leads back to the main line.
+    pub fn next_destination(&self, resources: &ResourceManager) -> Destination {
+        let branch = match resources.get_branch(self.branch) {
+            Some(branch) if self.depth < branch.last_depth() => self.branch,
+            _ => MAIN_BRANCH,
+        };
+
+        Destination {
+            branch,
+            depth: self.depth + 1,
+        }
+    }
+
     pub fn is_tile_sight_blocking(
         &self,
         coordinate: Coordinate,
@@ -88,16 +182,25 @@ impl GameMap {
         tile.los == Los::Block
     }
 
+    pub fn is_tile_sight_obstructing(&self, coordinate: Coordinate, resources: &ResourceManager) -> bool {
+        self.get_tile_at(coordinate, resources)
+            .is_some_and(|tile| tile.los == Los::Obstruct)
+    }
+
     pub fn is_tile_explored(&self, coordinate: Coordinate) -> bool {
         self.explored.borrow().contains(&coordinate)
     }
 
-    pub fn is_tile_explored_from_index(&self, index: u32) -> bool {
-        let coordinate = Coordinate {
-            x: (index % self.width) as i32,
-            y: (index / self.width) as i32,
-        };
-        self.explored.borrow().contains(&coordinate)
+    pub fn is_tile_visible(&self, coordinate: Coordinate) -> bool {
+        self.visible.borrow().contains(&coordinate)
+    }
+
+    pub fn get_light_level(&self, coordinate: Coordinate) -> u32 {
+        self.light.get(&coordinate).copied().unwrap_or(lighting::DARK)
+    }
+
+    pub fn get_remembered_images(&self, coordinate: Coordinate) -> Option<Vec<ImageID>> {
+        self.remembered.borrow().get(&coordinate).cloned()
     }
 
     pub fn is_tile_void(&self, coordinate: Coordinate) -> bool {
@@ -119,15 +222,163 @@ impl GameMap {
         }
     }
 
-    pub fn get_game_tile_from(&self, index: u32) -> TileID {
-        let coordinate = Coordinate {
-            x: (index % self.width) as i32,
-            y: (index / self.width) as i32,
+    /* MAP MUTATION
+        Tiles may change while the floor is played, when walls are dug out or blown up and floors collapse.
+        Every change goes through change_tile, which keeps the derived data in step: accumulated tile damage,
+        the room graph and the pathing grids. Entities are indexed by coordinate, so they need no update.
+     */
+
+    pub fn change_tile(&mut self, coordinate: Coordinate, tile_id: TileID, world: &mut World, resources: &ResourceManager) {
+        if !self.map.contains_key(&coordinate) {
+            return;
+        }
+
+        self.map.insert(coordinate, tile_id);
+        self.tile_damage.remove(&coordinate);
+        self.connect_rooms_through(coordinate, resources);
+        self.strand_units(coordinate, world, resources);
+
+        // monsters can act before the grids are rebuilt at the end of the turn
+        match world.get_player_position() {
+            Ok(player_position) => self.update_pathing_grid(player_position, world, resources),
+            Err(_) => self.pathing_grids.clear(),
+        }
+    }
+
+    // Returns whether the tile broke. The outermost ring of the map can't be broken.
+    pub fn damage_tile(&mut self, coordinate: Coordinate, amount: u32, world: &mut World, resources: &ResourceManager) -> bool {
+        if self.is_tile_on_border(coordinate) {
+            return false;
+        }
+
+        let Some(tile) = self.get_tile_at(coordinate, resources) else {
+            return false;
         };
-        match self.map.get(&coordinate) {
-            Some(tile) => *tile,
-            None => TileID(0),
+        let Some(health) = tile.health else {
+            return false;
+        };
+        let broken_into = tile.broken_into.unwrap_or(FLOOR_TILEID);
+
+        let damage = self.tile_damage.entry(coordinate).or_insert(0);
+        *damage += amount;
+        if *damage < health {
+            return false;
+        }
+
+        self.change_tile(coordinate, broken_into, world, resources);
+        true
+    }
+
+    // Returns whether the tile was dug out.
+    pub fn dig_tile(&mut self, coordinate: Coordinate, world: &mut World, resources: &ResourceManager) -> bool {
+        if self.is_tile_on_border(coordinate) {
+            return false;
+        }
+
+        let Some(tile) = self.get_tile_at(coordinate, resources) else {
+            return false;
+        };
+        if !tile.diggable {
+            return false;
         }
+        let broken_into = tile.broken_into.unwrap_or(FLOOR_TILEID);
+
+        self.change_tile(coordinate, broken_into, world, resources);
+        true
+    }
+
+    pub fn is_tile_on_border(&self, coordinate: Coordinate) -> bool {
+        coordinate.x <= 0
+            || coordinate.y <= 0
+            || coordinate.x >= self.width as i32 - 1
+            || coordinate.y >= self.height as i32 - 1
+    }
+
+    // An opening in a wall links every room touching it.
+    fn connect_rooms_through(&mut self, coordinate: Coordinate, resources: &ResourceManager) {
+        if !self.is_tile_walkable(coordinate, resources) {
+            return;
+        }
+
+        let touching: Vec<Coordinate> = [UP, DOWN, LEFT, RIGHT]
+            .into_iter()
+            .map(|direction| coordinate + direction)
+            .filter(|neighbor| self.is_tile_walkable(*neighbor, resources))
+            .chain([coordinate])
+            .collect();
+
+        let rooms: Vec<_> = self
+            .room_graph
+            .node_indices()
+            .filter(|index| {
+                let extends = self.room_graph[*index].extends;
+                touching.iter().any(|neighbor| extends.contains_point(*neighbor))
+            })
+            .collect();
+
+        for (first, second) in rooms.into_iter().tuple_combinations() {
+            self.room_graph.update_edge(first, second, ());
+        }
+    }
+
+    // Units left on a tile they can't cross scramble onto the closest free tile they can stand on, and get hurt doing so.
+    // Without one in reach they take the damage where they are and have to find their own way off.
+    fn strand_units(&self, coordinate: Coordinate, world: &mut World, resources: &ResourceManager) {
+        let Some(tile) = self.get_tile_at(coordinate, resources) else {
+            return;
+        };
+
+        for entity in world.get_entities_at_coordinate(coordinate) {
+            let movement = world.get_movement(entity);
+            if movement.can_cross(tile.passable) || world.borrow_entity_component::<Health>(entity).is_none() {
+                continue;
+            }
+
+            let landing = self.find_landing(coordinate, movement, world, resources);
+            if let Some(landing) = landing {
+                world.update_position(entity, landing);
+            }
+
+            let Some(health) = world.borrow_entity_component_mut::<Health>(entity) else {
+                continue;
+            };
+            health.sub_current(COLLAPSE_DAMAGE);
+            stats::record_damage(entity, entity, DamageType::Physical, COLLAPSE_DAMAGE, world);
+
+            if let Some(stats) = world.borrow_entity_component_mut::<RunStats>(entity) {
+                stats.last_hit_by = Some(tile.name.clone());
+            }
+
+            if !self.is_tile_visible(coordinate) {
+                continue;
+            }
+            let tile_name = tile.name.to_lowercase();
+            let message = match (world.get_player_id().ok() == Some(entity), landing) {
+                (true, Some(_)) => format!("You scramble clear of the {tile_name} and take {COLLAPSE_DAMAGE} damage."),
+                (true, None) => format!("You are caught in the {tile_name} and take {COLLAPSE_DAMAGE} damage."),
+                (false, _) => {
+                    let Some(Name(name)) = world.borrow_entity_component::<Name>(entity) else {
+                        continue;
+                    };
+                    match landing {
+                        Some(_) => format!("{name} scrambles clear of the {tile_name} and takes {COLLAPSE_DAMAGE} damage."),
+                        None => format!("{name} is caught in the {tile_name} and takes {COLLAPSE_DAMAGE} damage."),
+                    }
+                }
+            };
+            logger::log_message(&message);
+        }
+    }
+
+    // The closest tile around the coordinate the movement can stand on safely, without anyone in the way.
+    fn find_landing(&self, coordinate: Coordinate, movement: Movement, world: &World, resources: &ResourceManager) -> Option<Coordinate> {
+        (-STRANDED_REACH..=STRANDED_REACH)
+            .cartesian_product(-STRANDED_REACH..=STRANDED_REACH)
+            .map(|(y, x)| coordinate + Coordinate { x, y })
+            .filter(|candidate| self.is_tile_passable(*candidate, movement, resources))
+            .filter(|candidate| !self.is_tile_hazardous(*candidate, movement, resources))
+            .filter(|candidate| world.get_blocking_entity(*candidate).is_none())
+            .min_by(|a, b| coordinate.distance(*a).total_cmp(&coordinate.distance(*b)))
     }
 
     pub fn update_pathing_grid(
@@ -136,17 +387,107 @@ impl GameMap {
         world: &World,
         resources: &ResourceManager,
     ) {
-        let safe_pathing_grid = calculate_pathing_grid(
-            destination,
-            self,
-            world,
-            resources,
-            true,
-            false,
-            std::u32::MAX,
-        );
+        let mut movements: HashSet<Movement> = HashSet::from([Movement::default()]);
+        if let Some(monsters) = world.borrow_component_vec::<Monster>() {
+            let monster_movements = monsters
+                .iter()
+                .enumerate()
+                .filter(|(_, monster)| monster.is_some())
+                .map(|(entity, _)| world.get_movement(entity));
+            movements.extend(monster_movements);
+        }
+
+        self.pathing_grids = movements
+            .into_iter()
+            .map(|movement| {
+                let safe_pathing_grid = calculate_pathing_grid(
+                    destination,
+                    self,
+                    world,
+                    resources,
+                    movement,
+                    true,
+                    false,
+                    std::u32::MAX,
+                );
+                (movement, safe_pathing_grid)
+            })
+            .collect();
+    }
+
+    pub fn get_pathing_step(&self, coordinate: Coordinate, movement: Movement) -> Option<Coordinate> {
+        self.pathing_grids.get(&movement)?.get(&coordinate).copied()
+    }
+
+    // Walkable tiles of the room with these extends, following its shape.
+    pub fn get_room_floor(&self, extends: &BoxExtends, resources: &ResourceManager) -> Vec<Coordinate> {
+        let shape = self
+            .room_graph
+            .node_weights()
+            .find(|room| room.extends == *extends)
+            .map(|room| room.shape)
+            .unwrap_or_default();
+
+        shape
+            .floor_cells(extends)
+            .into_iter()
+            .filter(|coordinate| self.is_tile_walkable(*coordinate, resources))
+            .sorted()
+            .collect()
+    }
+
+    // Every tile that can be walked to from the start. Entities such as doors are ignored.
+    pub fn get_walkable_region(&self, start: Coordinate, resources: &ResourceManager) -> HashSet<Coordinate> {
+        let mut region = HashSet::new();
+        if !self.is_tile_walkable(start, resources) {
+            return region;
+        }
+
+        let mut fill_queue = VecDeque::from([start]);
+        region.insert(start);
+
+        while let Some(coordinate) = fill_queue.pop_front() {
+            for direction in [UP, DOWN, LEFT, RIGHT] {
+                let neighbor = coordinate + direction;
+                if !region.contains(&neighbor) && self.is_tile_walkable(neighbor, resources) {
+                    region.insert(neighbor);
+                    fill_queue.push_back(neighbor);
+                }
+            }
+        }
+
+        region
+    }
+
+    // Sees from the coordinate: visible tiles become explored and remembered as they are now.
+    pub fn update_field_of_view(&mut self, origin: Coordinate, world: &World, resources: &ResourceManager) {
+        self.light = lighting::light_map(self, world, resources);
+
+        // darkness hides everything but the tiles right next to the player
+        let visible: HashSet<Coordinate> = fov::field_of_view(origin, fov::FOV_RADIUS, self, world, resources)
+            .into_iter()
+            .filter(|coordinate| {
+                self.get_light_level(*coordinate) > lighting::DARK
+                    || origin.distance(*coordinate) <= lighting::DARK_SIGHT_RADIUS
+            })
+            .collect();
+
+        for coordinate in &visible {
+            let tile = self
+                .get_tile_at(*coordinate, resources)
+                .map_or(GameTile::default().image, |tile| tile.image);
+            let entities = world
+                .get_entities_at_coordinate(*coordinate)
+                .into_iter()
+                .filter(|entity| world.borrow_entity_component::<Monster>(*entity).is_none())
+                .filter_map(|entity| world.borrow_entity_component::<ImageID>(entity).copied());
+
+            let images = std::iter::once(tile).chain(entities).collect();
+            self.remembered.borrow_mut().insert(*coordinate, images);
+            self.explored.borrow_mut().insert(*coordinate);
+        }
 
-        self.pathing_grid = safe_pathing_grid;
+        *self.visible.borrow_mut() = visible;
     }
 
     pub fn explore_room(&mut self, coordinate: Coordinate) {
@@ -164,6 +505,23 @@ impl GameMap {
         };
     }
 
+    // Reveals the room at the coordinate, or only the surrounding tiles when outside of any room.
+    // Floors without doors, like caves, rely on this to be explored as the player walks.
+    pub fn explore_surroundings(&mut self, coordinate: Coordinate) {
+        if self.get_room_at_coordinate(coordinate).is_some() {
+            self.explore_room(coordinate);
+            return;
+        }
+
+        let surroundings = (-1..=1)
+            .cartesian_product(-1..=1)
+            .map(|(x, y)| Coordinate { x, y } + coordinate);
+
+        for neighbor in surroundings {
+            self.explored.borrow_mut().insert(neighbor);
+        }
+    }
+
     pub fn explore_hallway(
         &mut self,
         coordinate: Coordinate,
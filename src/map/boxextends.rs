@@ -1,7 +1,8 @@
 use num::clamp;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use rand_distr::{Distribution, Uniform};
 
+use crate::rng::game_rng;
 use super::utils::{Axis, Coordinate};
 use crate::error::Result;
 
@@ -113,8 +114,8 @@ pub fn random_subbox(area: &BoxExtends, min_side_length: i32) -> BoxExtends {
     let shrink_allowance_x = (old_x_size - min_side_length).max(0);
     let shrink_allowance_y = (old_y_size - min_side_length).max(0);
 
-    let random_x: f64 = distribution.sample(&mut thread_rng());
-    let random_y: f64 = distribution.sample(&mut thread_rng());
+    let random_x: f64 = distribution.sample(&mut game_rng());
+    let random_y: f64 = distribution.sample(&mut game_rng());
 
     let shrink_x = (random_x * shrink_allowance_x as f64) as i32;
     let shrink_y = (random_y * shrink_allowance_y as f64) as i32;
@@ -122,13 +123,13 @@ pub fn random_subbox(area: &BoxExtends, min_side_length: i32) -> BoxExtends {
     let offset_x: i32;
     match shrink_x <= 0 {
         true => offset_x = 0,
-        false => offset_x = thread_rng().gen_range(0..=shrink_x),
+        false => offset_x = game_rng().gen_range(0..=shrink_x),
     }
 
     let offset_y: i32;
     match shrink_y <= 0 {
         true => offset_y = 0,
-        false => offset_y = thread_rng().gen_range(0..=shrink_y),
+        false => offset_y = game_rng().gen_range(0..=shrink_y),
     }
 
     let top_left = Coordinate {
@@ -167,14 +168,14 @@ pub fn split_box(area: &BoxExtends) -> Result<(BoxExtends, BoxExtends)> {
         return x * x * x * (x * (6.0 * x - 15.0) + 10.0);
     }
 
-    let split_axis = match thread_rng().gen_bool(smootherstep(side_ratio)) {
+    let split_axis = match game_rng().gen_bool(smootherstep(side_ratio)) {
         true => Axis::Horizontal,
         false => Axis::Vertical,
     };
 
     let least_margin = 0.35;
 
-    let mut rng = rand::thread_rng()
+    let mut rng = game_rng()
         .sample_iter::<f32, _>(rand_distr::StandardNormal)
         .map(|val| val.clamp(least_margin, 1.0 - least_margin));
 
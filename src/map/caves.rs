@@ -0,0 +1,285 @@
+use itertools::Itertools;
+use log::warn;
+use petgraph::graph::NodeIndex;
+use rand::Rng;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::rng::game_rng;
+use crate::{
+    resources::{id::{BranchID, ThemeID}, ResourceManager, FLOOR_TILEID, WALL_TILEID},
+    spawning,
+};
+
+use super::{
+    boxextends::BoxExtends,
+    generation::{self, GenerationParameters, RoomGraph},
+    room::Room,
+    utils::{Coordinate, DOWN, LEFT, RIGHT, UP},
+    GameMap,
+};
+
+/* CAVES
+    Caves are grown with a cellular automaton: the map starts out as random noise and is smoothed
+    until the open areas clump together. Pockets cut off from the largest cave are tunneled to it.
+
+    The result is cut along the same binary space partitioning the regular generator uses.
+    The largest patch of floor inside each leaf becomes a room, so spawn tables, exploration
+    and the entity BSP work the same as on regular floors. Caves have no doors.
+ */
+
+const MAX_CAVE_ATTEMPTS: u32 = 10;
+const MIN_CAVE_ROOMS: usize = 2;
+// Pockets smaller than this are filled in instead of tunneled to.
+const MIN_POCKET_SIZE: usize = 4;
+
+type Cave = HashSet<Coordinate>;
+
+pub fn generate_caves(
+    depth: u32,
+    parameters: &GenerationParameters,
+    theme: Option<ThemeID>,
+    branch: BranchID,
+    resources: &ResourceManager,
+) -> (GameMap, RoomGraph) {
+    let GenerationParameters { size_x, size_y, .. } = *parameters;
+
+    let mut attempts = 0;
+    let (bsp, cave, graph) = loop {
+        attempts += 1;
+
+        let bsp = generation::binary_space_partitioning(size_x, size_y, parameters.bsp_depth);
+        let cave = grow_cave(parameters);
+        let cave = connect_pockets(cave);
+        let rooms = rooms_from_bsp_leaves(&bsp, &cave, parameters.min_room_area);
+
+        if rooms.len() >= MIN_CAVE_ROOMS || attempts >= MAX_CAVE_ATTEMPTS {
+            if rooms.len() < MIN_CAVE_ROOMS {
+                warn!("Cave generation gave up with only {} rooms", rooms.len());
+            }
+            let graph = connect_rooms(rooms, &cave);
+            break (bsp, cave, graph);
+        }
+    };
+
+    let mut map = draw_cave(&cave, size_x, size_y, depth);
+    map.room_graph = graph;
+    map.theme = theme;
+    map.branch = branch;
+
+    let map = spawning::flood_fill_spawn_tables(&map, resources);
+    (map, bsp)
+}
+
+fn grow_cave(parameters: &GenerationParameters) -> Cave {
+    let GenerationParameters { size_x, size_y, .. } = *parameters;
+    let mut rng = game_rng();
+
+    // the outermost ring always stays solid
+    let mut cave: Cave = interior_coordinates(size_x, size_y)
+        .filter(|_| !rng.gen_bool(parameters.cave_fill_chance))
+        .collect();
+
+    for _ in 0..parameters.cave_smoothing_steps {
+        cave = smooth_cave(&cave, size_x, size_y);
+    }
+
+    cave
+}
+
+// One automaton step: crowded cells fill in, open cells hollow out, the rest keep their state.
+fn smooth_cave(cave: &Cave, size_x: u32, size_y: u32) -> Cave {
+    interior_coordinates(size_x, size_y)
+        .filter(|coordinate| {
+            let walls = (-1..=1)
+                .flat_map(|x| (-1..=1).map(move |y| Coordinate { x, y }))
+                .filter(|offset| *offset != Coordinate::zero())
+                .filter(|offset| !cave.contains(&(*coordinate + *offset)))
+                .count();
+
+            match walls {
+                0..=3 => true,
+                4 => cave.contains(coordinate),
+                _ => false,
+            }
+        })
+        .collect()
+}
+
+fn interior_coordinates(size_x: u32, size_y: u32) -> impl Iterator<Item = Coordinate> {
+    (1..size_x as i32 - 1).flat_map(move |x| (1..size_y as i32 - 1).map(move |y| Coordinate { x, y }))
+}
+
+// Ensures every floor tile can be reached from every other one.
+fn connect_pockets(mut cave: Cave) -> Cave {
+    let mut pockets = connected_regions(&cave);
+    pockets.sort_by_key(|pocket| std::cmp::Reverse(pocket.len()));
+
+    let mut pockets = pockets.into_iter();
+    let Some(mut main_cave) = pockets.next() else {
+        return cave;
+    };
+
+    for pocket in pockets {
+        if pocket.len() < MIN_POCKET_SIZE {
+            for coordinate in pocket {
+                cave.remove(&coordinate);
+            }
+            continue;
+        }
+
+        let closest_pair = pocket
+            .iter()
+            .flat_map(|start| main_cave.iter().map(move |end| (*start, *end)))
+            .min_by_key(|(start, end)| ((start.x - end.x).abs() + (start.y - end.y).abs(), *start, *end));
+
+        if let Some((start, end)) = closest_pair {
+            for coordinate in tunnel_between(start, end) {
+                cave.insert(coordinate);
+                main_cave.insert(coordinate);
+            }
+        }
+        main_cave.extend(pocket);
+    }
+
+    cave
+}
+
+// Horizontal first, then vertical.
+fn tunnel_between(start: Coordinate, end: Coordinate) -> Vec<Coordinate> {
+    let (low_x, high_x) = (start.x.min(end.x), start.x.max(end.x));
+    let (low_y, high_y) = (start.y.min(end.y), start.y.max(end.y));
+
+    let horizontal = (low_x..=high_x).map(|x| Coordinate { x, y: start.y });
+    let vertical = (low_y..=high_y).map(|y| Coordinate { x: end.x, y });
+
+    horizontal.chain(vertical).collect()
+}
+
+// Groups the given tiles into 4-connected regions, ordered by their smallest coordinate.
+fn connected_regions(tiles: &Cave) -> Vec<Cave> {
+    let mut unvisited: Cave = tiles.clone();
+    let mut regions = vec![];
+
+    while let Some(start) = unvisited.iter().min().copied() {
+        let mut region = Cave::new();
+        let mut fill_queue = VecDeque::from([start]);
+        unvisited.remove(&start);
+
+        while let Some(coordinate) = fill_queue.pop_front() {
+            region.insert(coordinate);
+
+            for direction in [UP, DOWN, LEFT, RIGHT] {
+                let neighbor = coordinate + direction;
+                if unvisited.remove(&neighbor) {
+                    fill_queue.push_back(neighbor);
+                }
+            }
+        }
+
+        regions.push(region);
+    }
+
+    regions
+}
+
+// The largest patch of floor inside each leaf becomes a room, boxed in by its bounding box.
+fn rooms_from_bsp_leaves(bsp: &RoomGraph, cave: &Cave, min_room_area: i32) -> Vec<(Room, Cave)> {
+    generation::leaves_from_bsp(bsp)
+        .filter_map(|index| {
+            let leaf = bsp[index].extends;
+            let inside: Cave = cave
+                .iter()
+                .filter(|coordinate| {
+                    leaf.top_left.x < coordinate.x
+                        && coordinate.x < leaf.bottom_right.x
+                        && leaf.top_left.y < coordinate.y
+                        && coordinate.y < leaf.bottom_right.y
+                })
+                .copied()
+                .collect();
+
+            let region = connected_regions(&inside)
+                .into_iter()
+                .max_by_key(|region| region.len())?;
+
+            if region.len() as i32 <= min_room_area {
+                return None;
+            }
+
+            Some((Room::new(bounding_box(&region)), region))
+        })
+        .collect()
+}
+
+// Bounding box of the region with a one tile border, matching how rooms include their walls.
+fn bounding_box(region: &Cave) -> BoxExtends {
+    let min_x = region.iter().map(|coordinate| coordinate.x).min().unwrap_or(0);
+    let max_x = region.iter().map(|coordinate| coordinate.x).max().unwrap_or(0);
+    let min_y = region.iter().map(|coordinate| coordinate.y).min().unwrap_or(0);
+    let max_y = region.iter().map(|coordinate| coordinate.y).max().unwrap_or(0);
+
+    BoxExtends {
+        top_left: Coordinate { x: min_x - 1, y: min_y - 1 },
+        bottom_right: Coordinate { x: max_x + 1, y: max_y + 1 },
+    }
+}
+
+// Two rooms are neighbors when a walk between them doesn't pass through a third room.
+fn connect_rooms(rooms: Vec<(Room, Cave)>, cave: &Cave) -> RoomGraph {
+    let mut graph = RoomGraph::default();
+    let mut owners: HashMap<Coordinate, NodeIndex> = HashMap::new();
+    let mut regions = vec![];
+
+    for (room, region) in rooms {
+        let index = graph.add_node(room);
+        for coordinate in &region {
+            owners.insert(*coordinate, index);
+        }
+        regions.push((index, region));
+    }
+
+    for (index, region) in &regions {
+        let mut visited: Cave = region.clone();
+        let mut fill_queue: VecDeque<Coordinate> = region.iter().copied().sorted().collect();
+
+        while let Some(coordinate) = fill_queue.pop_front() {
+            for direction in [UP, DOWN, LEFT, RIGHT] {
+                let neighbor = coordinate + direction;
+                if !cave.contains(&neighbor) || visited.contains(&neighbor) {
+                    continue;
+                }
+
+                match owners.get(&neighbor) {
+                    Some(owner) if owner != index => {
+                        graph.update_edge(*index, *owner, ());
+                    }
+                    Some(_) => {}
+                    None => {
+                        visited.insert(neighbor);
+                        fill_queue.push_back(neighbor);
+                    }
+                }
+            }
+        }
+    }
+
+    graph
+}
+
+fn draw_cave(cave: &Cave, size_x: u32, size_y: u32, depth: u32) -> GameMap {
+    let mut map = GameMap::new(size_x, size_y);
+    map.depth = depth;
+
+    for x in 0..size_x as i32 {
+        for y in 0..size_y as i32 {
+            let coordinate = Coordinate { x, y };
+            let tile = match cave.contains(&coordinate) {
+                true => FLOOR_TILEID,
+                false => WALL_TILEID,
+            };
+            map.set_game_tile(coordinate, tile);
+        }
+    }
+
+    map
+}
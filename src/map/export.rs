@@ -0,0 +1,174 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use petgraph::{
+    visit::{EdgeRef, IntoNodeReferences},
+    Graph, Undirected,
+};
+use serde::Serialize;
+
+use crate::{
+    component::Name,
+    error::Result,
+    resources::{ResourceManager, MAIN_BRANCH},
+    spawning::spawn_all_entities,
+    world::World,
+};
+
+use super::{
+    boxextends::BoxExtends,
+    generation::{self, RoomGraph},
+    utils::Coordinate,
+    GameMap,
+};
+
+/* EXPORT
+    Writes the graphs behind a level as DOT for graphviz and as JSON, to see why generation placed things where it did.
+    Every graph gets a pair of files in the target directory:
+    - room_graph: the rooms with their extends, depth from the player room, template and extra spawn
+    - entity_bsp: the tree the world sorts entities into, with the entities of each leaf
+    - partition: the raw partition tree the rooms were cut from
+ */
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedGraph {
+    pub nodes: Vec<ExportedNode>,
+    pub edges: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedNode {
+    pub index: usize,
+    pub top_left: Coordinate,
+    pub bottom_right: Coordinate,
+    pub details: BTreeMap<String, String>,
+}
+
+impl ExportedNode {
+    fn new(index: usize, extends: &BoxExtends) -> Self {
+        Self {
+            index,
+            top_left: extends.top_left,
+            bottom_right: extends.bottom_right,
+            details: BTreeMap::new(),
+        }
+    }
+
+    fn with(mut self, key: &str, value: impl ToString) -> Self {
+        self.details.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+impl ExportedGraph {
+    fn from_graph<N>(graph: &Graph<N, (), Undirected>, node: impl Fn(usize, &N) -> ExportedNode) -> Self {
+        Self {
+            nodes: graph
+                .node_references()
+                .map(|(index, weight)| node(index.index(), weight))
+                .collect(),
+            edges: graph
+                .edge_references()
+                .map(|edge| (edge.source().index(), edge.target().index()))
+                .collect(),
+        }
+    }
+
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = format!("graph {name} {{\n    node [shape=box, fontname=monospace];\n");
+
+        for node in &self.nodes {
+            let mut label = format!(
+                "#{} ({}, {}) - ({}, {})",
+                node.index, node.top_left.x, node.top_left.y, node.bottom_right.x, node.bottom_right.y
+            );
+            for (key, value) in &node.details {
+                label.push_str(&format!("\\n{key}: {value}"));
+            }
+            dot.push_str(&format!("    {} [label=\"{}\"];\n", node.index, label.replace('"', "'")));
+        }
+
+        for (a, b) in &self.edges {
+            dot.push_str(&format!("    {a} -- {b};\n"));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn write_to(&self, directory: &Path, name: &str) -> Result<()> {
+        fs::write(directory.join(format!("{name}.dot")), self.to_dot(name))?;
+        fs::write(directory.join(format!("{name}.json")), self.to_json()?)?;
+        Ok(())
+    }
+}
+
+pub fn export_room_graph(graph: &RoomGraph) -> ExportedGraph {
+    ExportedGraph::from_graph(graph, |index, room| {
+        let mut node = ExportedNode::new(index, &room.extends)
+            .with("shape", format!("{:?}", room.shape))
+            .with("doors", room.door_locations.len());
+        if let Some(room_depth) = room.room_depth {
+            node = node.with("room_depth", room_depth);
+        }
+        if let Some(template) = room.template {
+            node = node.with("template", template.0);
+        }
+        if let Some(extra_spawn) = &room.extra_spawn {
+            node = node.with("extra_spawn", format!("{extra_spawn:?}"));
+        }
+        if let Some(vault) = room.vault {
+            node = node.with("vault", vault.0);
+        }
+        node
+    })
+}
+
+pub fn export_entity_bsp(world: &World) -> ExportedGraph {
+    ExportedGraph::from_graph(world.get_bsp(), |index, container| {
+        let mut node = ExportedNode::new(index, &container.extends);
+        if !container.entities.is_empty() {
+            let entities: Vec<String> = container
+                .entities
+                .iter()
+                .map(|entity| match world.borrow_entity_component::<Name>(*entity) {
+                    Some(name) => format!("{entity} {}", name.0),
+                    None => entity.to_string(),
+                })
+                .collect();
+            node = node.with("entities", entities.join(", "));
+        }
+        node
+    })
+}
+
+pub fn export_partition(partition: &RoomGraph) -> ExportedGraph {
+    ExportedGraph::from_graph(partition, |index, leaf| {
+        let extends = &leaf.extends;
+        ExportedNode::new(index, extends)
+            .with("width", extends.bottom_right.x - extends.top_left.x + 1)
+            .with("height", extends.bottom_right.y - extends.top_left.y + 1)
+    })
+}
+
+// Writes the graphs of a level that is already being played. The partition is gone by then.
+pub fn export_level(map: &GameMap, world: &World, directory: &Path) -> Result<()> {
+    fs::create_dir_all(directory)?;
+    export_room_graph(&map.room_graph).write_to(directory, "room_graph")?;
+    export_entity_bsp(world).write_to(directory, "entity_bsp")?;
+    Ok(())
+}
+
+// Generates a fresh level on the main line and writes all of its graphs.
+pub fn export_generated_level(depth: u32, directory: &Path, resources: &ResourceManager) -> Result<()> {
+    let (map, partition) = generation::generate_partitioned(depth, MAIN_BRANCH, resources);
+    let mut world = World::new_with(generation::entity_bsp_from_room_bsp(partition.clone()));
+    spawn_all_entities(&map, &mut world, resources)?;
+
+    export_level(&map, &world, directory)?;
+    export_partition(&partition).write_to(directory, "partition")?;
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use std::collections::HashSet;
+
+use crate::{resources::ResourceManager, world::World};
+
+use super::{utils::Coordinate, GameMap};
+
+/* FIELD OF VIEW
+    Recursive shadowcasting: each of the eight octants around the origin is scanned row by row,
+    and every tile blocking sight narrows the range of slopes the rows behind it can still see.
+    Walls and sight blocking entities like closed doors are visible themselves, but hide what lies behind them.
+ */
+
+pub const FOV_RADIUS: i32 = 10;
+
+// Maps the octant's rows and columns onto map directions: (xx, xy, yx, yy).
+const OCTANTS: [(i32, i32, i32, i32); 8] = [
+    (1, 0, 0, 1),
+    (0, 1, 1, 0),
+    (0, -1, 1, 0),
+    (-1, 0, 0, 1),
+    (-1, 0, 0, -1),
+    (0, -1, -1, 0),
+    (0, 1, -1, 0),
+    (1, 0, 0, -1),
+];
+
+pub fn field_of_view(
+    origin: Coordinate,
+    radius: i32,
+    map: &GameMap,
+    world: &World,
+    resources: &ResourceManager,
+) -> HashSet<Coordinate> {
+    let is_blocking = |coordinate: Coordinate| {
+        map.is_tile_sight_blocking(coordinate, resources) || world.get_sight_blocking_entity(coordinate).is_some()
+    };
+
+    let mut scan = Shadowcast {
+        origin,
+        radius,
+        is_blocking: &is_blocking,
+        visible: HashSet::from([origin]),
+    };
+    for octant in OCTANTS {
+        scan.cast_light(1, 1.0, 0.0, octant);
+    }
+
+    let mut visible = scan.visible;
+    visible.retain(|coordinate| map.map.contains_key(coordinate));
+    visible
+}
+
+struct Shadowcast<'a> {
+    origin: Coordinate,
+    radius: i32,
+    is_blocking: &'a dyn Fn(Coordinate) -> bool,
+    visible: HashSet<Coordinate>,
+}
+
+impl Shadowcast<'_> {
+    // Scans the rows of one octant from 'row' outwards, between the slopes 'start' and 'end'.
+    fn cast_light(&mut self, row: i32, mut start: f64, end: f64, octant: (i32, i32, i32, i32)) {
+        if start < end {
+            return;
+        }
+
+        let (xx, xy, yx, yy) = octant;
+        let mut new_start = 0.0;
+        for distance in row..=self.radius {
+            let delta_y = -distance;
+            let mut blocked = false;
+
+            for delta_x in -distance..=0 {
+                let left_slope = (delta_x as f64 - 0.5) / (delta_y as f64 + 0.5);
+                let right_slope = (delta_x as f64 + 0.5) / (delta_y as f64 - 0.5);
+                if start < right_slope {
+                    continue;
+                }
+                if end > left_slope {
+                    break;
+                }
+
+                let coordinate = Coordinate {
+                    x: self.origin.x + delta_x * xx + delta_y * xy,
+                    y: self.origin.y + delta_x * yx + delta_y * yy,
+                };
+                if delta_x * delta_x + delta_y * delta_y <= self.radius * self.radius {
+                    self.visible.insert(coordinate);
+                }
+
+                let is_blocking = (self.is_blocking)(coordinate);
+                if blocked {
+                    if is_blocking {
+                        new_start = right_slope;
+                        continue;
+                    }
+                    blocked = false;
+                    start = new_start;
+                } else if is_blocking && distance < self.radius {
+                    // everything past this blocker is scanned separately, up to its left edge
+                    blocked = true;
+                    self.cast_light(distance + 1, start, left_slope, octant);
+                    new_start = right_slope;
+                }
+            }
+
+            if blocked {
+                break;
+            }
+        }
+    }
+}
@@ -1,51 +1,160 @@
-use petgraph::{algo, graph::NodeIndex, visit::IntoNodeReferences, Graph};
+use log::{info, warn};
+use petgraph::{
+    algo,
+    graph::NodeIndex,
+    visit::IntoNodeReferences,
+    Graph,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
+use crate::rng::game_rng;
 use crate::{
-    resources::{ResourceManager, DEFAULT_TILEID, FLOOR_TILEID, WALL_TILEID},
-    spawning,
+    resources::{id::{BranchID, ThemeID, VaultID}, ResourceManager, DEFAULT_TILEID, FLOOR_TILEID, WALL_TILEID},
+    spawning::{
+        self,
+        spawnentry::SpawnEntryType,
+        vault::{Vault, VaultGlyph},
+    },
     world::EntityGraph,
 };
 
 use super::{
     boxextends::{self, BoxExtends},
-    room::Room,
-    utils::{Coordinate, DOWN, LEFT, RIGHT, UP},
+    caves,
+    room::{QuestGenerationData, Room, RoomGenerationData},
+    shape::{RoomShape, RoomShapeWeights},
+    theme,
+    utils::{Axis, Coordinate, DOWN, LEFT, RIGHT, UP},
     GameMap,
 };
 
 pub type RoomGraph = Graph<Room, (), petgraph::Undirected>;
 
-pub fn generate_new(
-    size_x: u32,
-    size_y: u32,
+const MAX_GENERATION_ATTEMPTS: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LevelStyle {
+    #[default]
+    Bsp,
+    Caves,
+}
+
+// Tuning for a single floor, read from generation.yaml through the ResourceManager.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GenerationParameters {
+    pub style: LevelStyle,
+    pub size_x: u32,
+    pub size_y: u32,
+    pub bsp_depth: u32,
+    pub min_room_side: i32,
+    pub min_room_area: i32,
+    pub neighbor_scan_distance: i32,
+    pub edge_threshold: usize,
+    pub max_edge_trim: usize,
+    pub cave_fill_chance: f64,
+    pub cave_smoothing_steps: u32,
+    pub vault_chance: f64,
+    pub room_shapes: RoomShapeWeights,
+    pub max_spawns_per_room: usize,
+    pub secret_door_chance: f64,
+}
+
+impl Default for GenerationParameters {
+    fn default() -> Self {
+        Self {
+            style: LevelStyle::Bsp,
+            size_x: 16 * 2,
+            size_y: 9 * 2,
+            bsp_depth: 4,
+            min_room_side: 5,
+            min_room_area: 6,
+            neighbor_scan_distance: 6,
+            edge_threshold: 4,
+            max_edge_trim: 2,
+            cave_fill_chance: 0.45,
+            cave_smoothing_steps: 4,
+            vault_chance: 0.0,
+            room_shapes: RoomShapeWeights::default(),
+            max_spawns_per_room: 12,
+            secret_door_chance: 0.0,
+        }
+    }
+}
+
+pub fn generate_new(depth: u32, branch: BranchID, resources: &ResourceManager) -> (GameMap, EntityGraph) {
+    let (map, partition) = generate_partitioned(depth, branch, resources);
+    (map, entity_bsp_from_room_bsp(partition))
+}
+
+// Like generate_new, but keeps the raw partition tree the entity BSP is made from.
+// Branches bring their own theme, the main line picks one fitting the depth.
+pub fn generate_partitioned(depth: u32, branch: BranchID, resources: &ResourceManager) -> (GameMap, RoomGraph) {
+    let parameters = resources.get_generation_parameters(depth);
+    let theme = match resources.get_branch(branch) {
+        Some(branch) => branch.theme,
+        None => resources.pick_theme(depth),
+    };
+    if let Some(theme) = theme.and_then(|theme| resources.get_theme(theme)) {
+        info!("Depth {depth} uses the {} theme", theme.name);
+    }
+
+    let (mut map, partition) = match parameters.style {
+        LevelStyle::Bsp => generate_bsp(depth, &parameters, theme, branch, resources),
+        LevelStyle::Caves => caves::generate_caves(depth, &parameters, theme, branch, resources),
+    };
+
+    theme::paint_theme(&mut map, resources);
+    (map, partition)
+}
+
+fn generate_bsp(
     depth: u32,
+    parameters: &GenerationParameters,
+    theme: Option<ThemeID>,
+    branch: BranchID,
     resources: &ResourceManager,
-) -> (GameMap, EntityGraph) {
-    let bsp: RoomGraph;
-    let mut graph: RoomGraph;
+) -> (GameMap, RoomGraph) {
+    let GenerationParameters { size_x, size_y, .. } = *parameters;
+
+    let mut attempts = 0;
     loop {
-        bsp = binary_space_partitioning(size_x, size_y, 4);
-        graph = make_rooms_from_bsp(&bsp, 5);
-        graph = prune_small_rooms(&graph, 6);
-        graph = make_connected_graph(&graph, 6);
-        graph = prune_edges(&graph, 4, 2);
+        attempts += 1;
+
+        let bsp = binary_space_partitioning(size_x, size_y, parameters.bsp_depth);
+        let mut graph = make_rooms_from_bsp(&bsp, parameters.min_room_side);
+        graph = prune_small_rooms(&graph, parameters.min_room_area);
+        graph = place_vaults(&bsp, &graph, depth, parameters.vault_chance, resources);
+        graph = assign_room_shapes(&graph, &parameters.room_shapes);
+        graph = make_connected_graph(&graph, parameters.neighbor_scan_distance);
+        graph = prune_edges(&graph, parameters.edge_threshold, parameters.max_edge_trim);
 
+        // drawing can't join rooms the graph doesn't connect, so start over instead
         let islands = algo::connected_components(&graph);
-        if islands == 1 {
-            break;
+        if islands != 1 && attempts < MAX_GENERATION_ATTEMPTS {
+            continue;
         }
-        break;
-    }
 
-    let map = draw_rooms_to_map(&graph, size_x, size_y, depth);
-    let map = add_doors_to_rooms(&map, resources);
-    let map = spawning::flood_fill_spawn_tables(&map, resources);
-    let bsp = entity_bsp_from_room_bsp(bsp);
-    (map, bsp)
+        let mut map = draw_rooms_to_map(&graph, size_x, size_y, depth, resources);
+        map.theme = theme;
+        map.branch = branch;
+        // join the rooms first, the spawn tables follow the room graph out from the player's room
+        repair_connectivity(&mut map, resources);
+        let map = spawning::flood_fill_spawn_tables(&map, resources);
+        let map = add_doors_to_rooms(&map, resources);
+
+        let is_goal_reachable = is_goal_reachable(&map, resources);
+        if is_goal_reachable || attempts >= MAX_GENERATION_ATTEMPTS {
+            if !is_goal_reachable {
+                warn!("Level generation gave up on connecting the player to the way down at depth {depth}");
+            }
+            return (map, bsp);
+        }
+    }
 }
 
-fn binary_space_partitioning(size_x: u32, size_y: u32, max_depth: u32) -> RoomGraph {
+pub fn binary_space_partitioning(size_x: u32, size_y: u32, max_depth: u32) -> RoomGraph {
     // Recursive algorithm for generating a binary space partitioning on BoxExtends.
     // Allows overlapping walls.
     let mut graph = RoomGraph::new_undirected();
@@ -101,7 +210,7 @@ fn make_rooms_from_bsp(bsp_tree: &RoomGraph, min_side_length: i32) -> RoomGraph
     graph
 }
 
-fn leaves_from_bsp<'a>(graph: &'a RoomGraph) -> impl Iterator<Item = NodeIndex> + 'a {
+pub fn leaves_from_bsp<'a>(graph: &'a RoomGraph) -> impl Iterator<Item = NodeIndex> + 'a {
     graph
         .node_indices()
         .filter(|index| graph.neighbors_undirected(*index).count() == 1)
@@ -175,6 +284,95 @@ fn prune_small_rooms(graph: &RoomGraph, threshold: i32) -> RoomGraph {
     pruned_graph
 }
 
+// Swaps rooms for vaults that fit into the leaf the room was made in. Each vault appears once per floor.
+fn place_vaults(
+    bsp_tree: &RoomGraph,
+    room_graph: &RoomGraph,
+    depth: u32,
+    vault_chance: f64,
+    resources: &ResourceManager,
+) -> RoomGraph {
+    let mut new_graph = RoomGraph::default();
+    new_graph.clone_from(room_graph);
+
+    let leaves: Vec<BoxExtends> = leaves_from_bsp(bsp_tree)
+        .map(|index| bsp_tree[index].extends)
+        .collect();
+    let mut used_vaults: HashSet<VaultID> = HashSet::new();
+    // Keep the player's start room free, vaults in it would never get their entities spawned.
+    let start_room = spawning::find_start_room(&new_graph);
+
+    for (index, room) in new_graph.node_weights_mut().enumerate() {
+        if start_room.is_some_and(|start| start.index() == index) || !game_rng().gen_bool(vault_chance) {
+            continue;
+        }
+
+        let Some(leaf) = leaves.iter().find(|leaf| leaf.contains(&room.extends)) else {
+            continue;
+        };
+
+        let room_data = RoomGenerationData {
+            room: (&*room).into(),
+            level_depth: depth,
+            room_depth: 0,
+        };
+        let quest_data = QuestGenerationData {};
+
+        let viable_vaults: Vec<(VaultID, &Vault)> = resources
+            .get_all_vaults()
+            .filter(|(id, vault)| {
+                !used_vaults.contains(id)
+                    && vault.fits_in(leaf)
+                    && vault.validate(&room_data, &quest_data)
+            })
+            .map(|(id, vault)| (*id, vault))
+            .collect();
+
+        if viable_vaults.len() == 0 {
+            continue;
+        }
+
+        let random_index = game_rng().gen_range(0..viable_vaults.len());
+        let (vault_id, vault) = viable_vaults[random_index];
+
+        let free_x = leaf.get_axis_size(Axis::Horizontal) - vault.width();
+        let free_y = leaf.get_axis_size(Axis::Vertical) - vault.height();
+        let top_left = leaf.top_left
+            + Coordinate {
+                x: game_rng().gen_range(0..=free_x),
+                y: game_rng().gen_range(0..=free_y),
+            };
+
+        room.extends = BoxExtends {
+            top_left,
+            bottom_right: top_left
+                + Coordinate {
+                    x: vault.width() - 1,
+                    y: vault.height() - 1,
+                },
+        };
+        room.vault = Some(vault_id);
+        used_vaults.insert(vault_id);
+    }
+
+    new_graph
+}
+
+// Vaults keep their own layout, every other room gets a shape that fits its box.
+fn assign_room_shapes(room_graph: &RoomGraph, weights: &RoomShapeWeights) -> RoomGraph {
+    let mut new_graph = RoomGraph::default();
+    new_graph.clone_from(room_graph);
+
+    for room in new_graph.node_weights_mut() {
+        if room.vault.is_some() {
+            continue;
+        }
+        room.shape = weights.pick_for(&room.extends);
+    }
+
+    new_graph
+}
+
 // Prune edges from rooms with edge_count over the threshold, attempting to maintain connectivity
 fn prune_edges(graph: &RoomGraph, edge_threshold: usize, max_trim_amount: usize) -> RoomGraph {
     let mut pruned_graph = RoomGraph::default();
@@ -205,7 +403,13 @@ fn prune_edges(graph: &RoomGraph, edge_threshold: usize, max_trim_amount: usize)
     pruned_graph
 }
 
-fn draw_rooms_to_map(graph: &RoomGraph, size_x: u32, size_y: u32, depth: u32) -> GameMap {
+fn draw_rooms_to_map(
+    graph: &RoomGraph,
+    size_x: u32,
+    size_y: u32,
+    depth: u32,
+    resources: &ResourceManager,
+) -> GameMap {
     let mut map = GameMap::new(size_x, size_y);
     map.room_graph = graph.clone();
     map.depth = depth;
@@ -213,12 +417,14 @@ fn draw_rooms_to_map(graph: &RoomGraph, size_x: u32, size_y: u32, depth: u32) ->
 
     // Drawing empty rooms
     for index in leaves {
-        let room_box: BoxExtends = match graph.node_weight(index) {
-            Some(weight) => weight.extends,
-            None => continue,
+        let Some(room) = graph.node_weight(index) else {
+            continue;
         };
 
-        draw_room(room_box, &mut map);
+        match room.vault.and_then(|vault| resources.get_vault(vault)) {
+            Some(vault) => draw_vault(room.extends, vault, &mut map),
+            None => draw_room(room.extends, room.shape, &mut map),
+        }
     }
 
     // Drawing corridors
@@ -233,32 +439,69 @@ fn draw_rooms_to_map(graph: &RoomGraph, size_x: u32, size_y: u32, depth: u32) ->
             &graph.node_weight(room_b).unwrap().extends,
         )
     }
+
+    tunnel_into_shaped_rooms(graph, &mut map);
     map
 }
 
-fn draw_room(room_box: BoxExtends, map: &mut GameMap) {
+fn draw_room(room_box: BoxExtends, shape: RoomShape, map: &mut GameMap) {
     let (left, top) = (room_box.top_left.x, room_box.top_left.y);
     let (right, bottom) = (room_box.bottom_right.x, room_box.bottom_right.y);
+    let floor = shape.floor_cells(&room_box);
 
     for x in left..=right {
-        // top row
-        map.set_game_tile(Coordinate { x, y: top }, WALL_TILEID);
-
-        // bottom row
-        map.set_game_tile(Coordinate { x, y: bottom }, WALL_TILEID);
-
-        for y in (top + 1)..bottom {
-            let tile;
-            if x == left || x == right {
-                tile = WALL_TILEID;
-            } else {
-                tile = FLOOR_TILEID;
+        for y in top..=bottom {
+            let coordinate = Coordinate { x, y };
+            let tile = match floor.contains(&coordinate) {
+                true => FLOOR_TILEID,
+                false => WALL_TILEID,
+            };
+            map.set_game_tile(coordinate, tile);
+        }
+    }
+}
+
+// Corridors stop at the outer wall, so shapes with filled corners need a tunnel from there to their floor.
+fn tunnel_into_shaped_rooms(graph: &RoomGraph, map: &mut GameMap) {
+    for room in graph.node_weights() {
+        if room.shape == RoomShape::Rectangle {
+            continue;
+        }
+
+        let floor = room.shape.floor_cells(&room.extends);
+        let (left, top) = (room.extends.top_left.x, room.extends.top_left.y);
+        let (right, bottom) = (room.extends.bottom_right.x, room.extends.bottom_right.y);
+
+        let horizontal_walls = (left + 1..right)
+            .flat_map(|x| [(Coordinate { x, y: top }, DOWN), (Coordinate { x, y: bottom }, UP)]);
+        let vertical_walls = (top + 1..bottom)
+            .flat_map(|y| [(Coordinate { x: left, y }, RIGHT), (Coordinate { x: right, y }, LEFT)]);
+
+        for (entrance, inwards) in horizontal_walls.chain(vertical_walls) {
+            if map.get_game_tile(entrance) != FLOOR_TILEID {
+                continue;
+            }
+
+            let mut current = entrance + inwards;
+            while room.extends.contains_point(current) && !floor.contains(&current) {
+                map.set_game_tile(current, FLOOR_TILEID);
+                current += inwards;
             }
-            map.set_game_tile(Coordinate { x: x, y: y }, tile);
         }
     }
 }
 
+// Spawnables are only placed once entities are spawned, the vault leaves floor under them.
+fn draw_vault(room_box: BoxExtends, vault: &Vault, map: &mut GameMap) {
+    for (offset, glyph) in vault.glyphs() {
+        let tile = match glyph {
+            VaultGlyph::Tile(tile) => tile,
+            VaultGlyph::Spawnable(_) => FLOOR_TILEID,
+        };
+        map.set_game_tile(room_box.top_left + offset, tile);
+    }
+}
+
 fn draw_path_between_rooms(map: &mut GameMap, box_a: &BoxExtends, box_b: &BoxExtends) {
     // case overlap in x
     let a_x_range: HashSet<i32> = HashSet::from_iter(box_a.top_left.x + 1..box_a.bottom_right.x);
@@ -266,7 +509,7 @@ fn draw_path_between_rooms(map: &mut GameMap, box_a: &BoxExtends, box_b: &BoxExt
     let x_range_overlap: HashSet<i32> = a_x_range.intersection(&b_x_range).map(|i| *i).collect();
 
     if x_range_overlap.len() > 0 {
-        let corridor_x = *x_range_overlap.iter().next().unwrap();
+        let corridor_x = pick_from_overlap(&x_range_overlap);
         let corridor_start = Coordinate {
             x: corridor_x,
             y: box_a.center().y,
@@ -285,7 +528,7 @@ fn draw_path_between_rooms(map: &mut GameMap, box_a: &BoxExtends, box_b: &BoxExt
     let y_range_overlap: HashSet<i32> = a_y_range.intersection(&b_y_range).map(|i| *i).collect();
 
     if y_range_overlap.len() > 0 {
-        let corridor_y = *y_range_overlap.iter().next().unwrap();
+        let corridor_y = pick_from_overlap(&y_range_overlap);
         let corridor_start = Coordinate {
             x: box_a.center().x,
             y: corridor_y,
@@ -296,6 +539,178 @@ fn draw_path_between_rooms(map: &mut GameMap, box_a: &BoxExtends, box_b: &BoxExt
         };
 
         draw_horizontal_corridor(corridor_start, corridor_end, map);
+        return;
+    }
+
+    draw_bent_corridor(map, box_a, box_b);
+}
+
+// Any shared column or row works for a straight corridor. Sorted first, so a seed always picks the same one.
+fn pick_from_overlap(overlap: &HashSet<i32>) -> i32 {
+    let mut overlap: Vec<i32> = overlap.iter().copied().collect();
+    overlap.sort();
+    overlap[game_rng().gen_range(0..overlap.len())]
+}
+
+// For rooms that don't share a row or column. When there is a gap between the rooms the corridor
+// bends twice inside it (Z), otherwise once at the corner between them (L).
+fn draw_bent_corridor(map: &mut GameMap, box_a: &BoxExtends, box_b: &BoxExtends) {
+    let (start, end) = (box_a.center(), box_b.center());
+
+    let (left_box, right_box) = match box_a.center().x < box_b.center().x {
+        true => (box_a, box_b),
+        false => (box_b, box_a),
+    };
+    let (top_box, bottom_box) = match box_a.center().y < box_b.center().y {
+        true => (box_a, box_b),
+        false => (box_b, box_a),
+    };
+    let gap_x = right_box.top_left.x - left_box.bottom_right.x;
+    let gap_y = bottom_box.top_left.y - top_box.bottom_right.y;
+
+    let corners = if gap_x >= 2 {
+        let middle_x = (left_box.bottom_right.x + right_box.top_left.x) / 2;
+        let corners = [Coordinate { x: middle_x, y: start.y }, Coordinate { x: middle_x, y: end.y }];
+
+        draw_horizontal_corridor(start, corners[0], map);
+        draw_vertical_corridor(corners[0], corners[1], map);
+        draw_horizontal_corridor(corners[1], end, map);
+        corners.to_vec()
+    } else if gap_y >= 2 {
+        let middle_y = (top_box.bottom_right.y + bottom_box.top_left.y) / 2;
+        let corners = [Coordinate { x: start.x, y: middle_y }, Coordinate { x: end.x, y: middle_y }];
+
+        draw_vertical_corridor(start, corners[0], map);
+        draw_horizontal_corridor(corners[0], corners[1], map);
+        draw_vertical_corridor(corners[1], end, map);
+        corners.to_vec()
+    } else {
+        let corner = Coordinate { x: end.x, y: start.y };
+
+        draw_horizontal_corridor(start, corner, map);
+        draw_vertical_corridor(corner, end, map);
+        vec![corner]
+    };
+
+    // the straight pieces only wall their own sides, leaving the outside of each bend open
+    for corner in corners {
+        wall_in(corner, map);
+    }
+}
+
+fn wall_in(coordinate: Coordinate, map: &mut GameMap) {
+    let surroundings = (-1..=1).flat_map(|x| (-1..=1).map(move |y| Coordinate { x, y } + coordinate));
+
+    for neighbor in surroundings {
+        if map.get_game_tile(neighbor) == DEFAULT_TILEID {
+            map.set_game_tile(neighbor, WALL_TILEID);
+        }
+    }
+}
+
+// A straight tunnel that cuts through whatever is in its way, horizontal first, then vertical.
+fn carve_tunnel(start: Coordinate, end: Coordinate, map: &mut GameMap) {
+    let (low_x, high_x) = (start.x.min(end.x), start.x.max(end.x));
+    let (low_y, high_y) = (start.y.min(end.y), start.y.max(end.y));
+
+    let horizontal = (low_x..=high_x).map(|x| Coordinate { x, y: start.y });
+    let vertical = (low_y..=high_y).map(|y| Coordinate { x: end.x, y });
+
+    for coordinate in horizontal.chain(vertical) {
+        map.set_game_tile(coordinate, FLOOR_TILEID);
+        wall_in(coordinate, map);
+    }
+}
+
+// The room the player starts in and the one holding the way down, or the artifact.
+fn find_objective_rooms(map: &GameMap) -> Option<(NodeIndex, NodeIndex)> {
+    let find_room = |is_objective: fn(&SpawnEntryType) -> bool| {
+        map.room_graph
+            .node_indices()
+            .find(|index| map.room_graph[*index].extra_spawn.as_ref().is_some_and(is_objective))
+    };
+
+    let start = find_room(|spawn| matches!(spawn, SpawnEntryType::Player(_)))?;
+    let goal = find_room(|spawn| matches!(spawn, SpawnEntryType::Stairs(_) | SpawnEntryType::Artifact(_)))?;
+    Some((start, goal))
+}
+
+fn are_rooms_connected(map: &GameMap, from: &Room, to: &Room, resources: &ResourceManager) -> bool {
+    let Some(start) = map.get_room_floor(&from.extends, resources).first().copied() else {
+        return false;
+    };
+
+    let region = map.get_walkable_region(start, resources);
+    map.get_room_floor(&to.extends, resources)
+        .iter()
+        .any(|coordinate| region.contains(coordinate))
+}
+
+fn is_goal_reachable(map: &GameMap, resources: &ResourceManager) -> bool {
+    let Some((start, goal)) = find_objective_rooms(map) else {
+        return true;
+    };
+
+    are_rooms_connected(map, &map.room_graph[start], &map.room_graph[goal], resources)
+}
+
+// Joins every room the drawn corridors left unreachable from the player's room, the room graph may even
+// have come out in pieces. The unreachable room closest to a reachable one gets a tunnel to it, then
+// reachability is checked again. Rooms a tunnel can't join are left for validation to reject.
+// The same layout always gets the same fix.
+fn repair_connectivity(map: &mut GameMap, resources: &ResourceManager) {
+    let Some(start) = spawning::find_start_room(&map.room_graph) else {
+        return;
+    };
+
+    let mut unjoinable: HashSet<NodeIndex> = HashSet::new();
+    loop {
+        let Some(origin) = map.get_room_floor(&map.room_graph[start].extends, resources).first().copied() else {
+            return;
+        };
+        let region = map.get_walkable_region(origin, resources);
+
+        let (reachable, unreachable): (Vec<NodeIndex>, Vec<NodeIndex>) =
+            map.room_graph.node_indices().partition(|index| {
+                map.get_room_floor(&map.room_graph[*index].extends, resources)
+                    .iter()
+                    .any(|coordinate| region.contains(coordinate))
+            });
+
+        let center = |index: NodeIndex| map.room_graph[index].extends.center();
+        let closest_pair = unreachable
+            .iter()
+            .filter(|index| !unjoinable.contains(index))
+            .flat_map(|room| reachable.iter().map(move |target| (*room, *target)))
+            .min_by(|(a_room, a_target), (b_room, b_target)| {
+                let a_distance = center(*a_room).distance(center(*a_target));
+                let b_distance = center(*b_room).distance(center(*b_target));
+                a_distance.total_cmp(&b_distance)
+            });
+        let Some((room, target)) = closest_pair else {
+            return;
+        };
+
+        // tunnel between the closest floor tiles, room centers can lie outside of shaped rooms
+        let from = map
+            .get_room_floor(&map.room_graph[room].extends, resources)
+            .into_iter()
+            .min_by(|a, b| a.distance(center(target)).total_cmp(&b.distance(center(target))))
+            .unwrap_or(center(room));
+        let to = map
+            .get_room_floor(&map.room_graph[target].extends, resources)
+            .into_iter()
+            .filter(|coordinate| region.contains(coordinate))
+            .min_by(|a, b| a.distance(from).total_cmp(&b.distance(from)))
+            .unwrap_or(center(target));
+
+        carve_tunnel(from, to, map);
+        map.room_graph.update_edge(room, target, ());
+
+        let start_room = &map.room_graph[start];
+        if !are_rooms_connected(map, start_room, &map.room_graph[room], resources) {
+            unjoinable.insert(room);
+        }
     }
 }
 
@@ -405,23 +820,16 @@ fn draw_horizontal_corridor(start: Coordinate, end: Coordinate, map: &mut GameMa
     }
 }
 
-fn check_door_conditions(coord: Coordinate, map: &GameMap, resources: &ResourceManager) -> bool {
+// A door needs a gap in a wall: walkable on two opposite sides and blocked on the other two.
+pub fn check_door_conditions(coord: Coordinate, map: &GameMap, resources: &ResourceManager) -> bool {
     if !map.is_tile_walkable(coord, resources) {
         return false;
     }
 
-    let directions = [UP, DOWN, LEFT, RIGHT];
-    let walkable_neighbors = directions
-        .iter()
-        .map(|dir| coord + *dir)
-        .filter(|neighbor| map.is_tile_walkable(*neighbor, resources))
-        .count();
-
-    if walkable_neighbors != 2 {
-        return false;
-    }
+    let is_walkable = |direction: Coordinate| map.is_tile_walkable(coord + direction, resources);
+    let (up, down, left, right) = (is_walkable(UP), is_walkable(DOWN), is_walkable(LEFT), is_walkable(RIGHT));
 
-    true
+    (up && down && !left && !right) || (left && right && !up && !down)
 }
 
 fn add_doors_to_rooms(map: &GameMap, resources: &ResourceManager) -> GameMap {
@@ -431,33 +839,19 @@ fn add_doors_to_rooms(map: &GameMap, resources: &ResourceManager) -> GameMap {
     for (node, room) in map.room_graph.node_references() {
         let (left, top) = (room.extends.top_left.x, room.extends.top_left.y);
         let (right, bottom) = (room.extends.bottom_right.x, room.extends.bottom_right.y);
-        let mut door_locations = vec![];
-
-        // Horizontal walls + corners
-        for x in left..=right {
-            let top_coord = Coordinate { x, y: top };
-            let bottom_coord = Coordinate { x, y: bottom };
-
-            if check_door_conditions(top_coord, map, resources) {
-                door_locations.push(top_coord);
-            }
-            if check_door_conditions(bottom_coord, map, resources) {
-                door_locations.push(bottom_coord);
-            }
-        }
-
-        // Vertical walls (not the corners)
-        for y in top + 1..bottom {
-            let left_coord = Coordinate { x: left, y };
-            let right_coord = Coordinate { x: right, y };
-
-            if check_door_conditions(left_coord, map, resources) {
-                door_locations.push(left_coord);
-            }
-            if check_door_conditions(right_coord, map, resources) {
-                door_locations.push(right_coord);
-            }
-        }
+        let floor = room.shape.floor_cells(&room.extends);
+
+        // Doors go into openings in the room's walls that lead onto its floor
+        let door_locations = (left..=right)
+            .flat_map(|x| (top..=bottom).map(move |y| Coordinate { x, y }))
+            .filter(|coord| !floor.contains(coord))
+            .filter(|coord| {
+                [UP, DOWN, LEFT, RIGHT]
+                    .iter()
+                    .any(|dir| floor.contains(&(*coord + *dir)))
+            })
+            .filter(|coord| check_door_conditions(*coord, map, resources))
+            .collect();
 
         let new_room = Room {
             door_locations,
@@ -471,7 +865,7 @@ fn add_doors_to_rooms(map: &GameMap, resources: &ResourceManager) -> GameMap {
     new_map
 }
 
-fn entity_bsp_from_room_bsp(room_graph: RoomGraph) -> EntityGraph {
+pub fn entity_bsp_from_room_bsp(room_graph: RoomGraph) -> EntityGraph {
     let mut bsp = EntityGraph::new_undirected();
 
     let nodes = room_graph
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::{
+    component::{
+        effect::{EffectHandler, EffectType},
+        LightSource, Position,
+    },
+    resources::ResourceManager,
+    world::World,
+};
+
+use super::{fov, utils::Coordinate, GameMap};
+
+/* LIGHTING
+    Every tile has a light level: dark, dim or bright.
+    Rooms start out with the ambient light of their template, everything outside of rooms like corridors is dim.
+    Light sources light up the tiles they can see on top of that, brightly close by and dimly towards the edge
+    of their radius. Burning entities shed a little light as well.
+
+    The player sees dark tiles only right next to them, and dim tiles are drawn darker.
+    Monsters have a harder time spotting the player the darker the tile the player stands on.
+ */
+
+pub const DARK: u32 = 0;
+pub const DIM_LIGHT: u32 = 1;
+pub const BRIGHT_LIGHT: u32 = 2;
+
+pub const DEFAULT_AMBIENT_LIGHT: u32 = BRIGHT_LIGHT;
+pub const CORRIDOR_LIGHT: u32 = DIM_LIGHT;
+pub const DARK_SIGHT_RADIUS: f64 = 1.5;
+
+const BURNING_LIGHT_RADIUS: u32 = 2;
+// Sources light brightly up to this part of their radius.
+const BRIGHT_PART_OF_RADIUS: f64 = 0.5;
+
+const DARK_SPOTTING_RANGE: f64 = 2.0;
+const DIM_SPOTTING_RANGE: f64 = 5.0;
+
+pub fn light_map(map: &GameMap, world: &World, resources: &ResourceManager) -> HashMap<Coordinate, u32> {
+    let mut light: HashMap<Coordinate, u32> = map
+        .map
+        .keys()
+        .map(|coordinate| (*coordinate, CORRIDOR_LIGHT))
+        .collect();
+
+    for room in map.room_graph.node_weights() {
+        let ambient_light = room
+            .template
+            .and_then(|template| resources.get_room_template(template))
+            .and_then(|template| template.ambient_light)
+            .unwrap_or(DEFAULT_AMBIENT_LIGHT);
+
+        let x_range = room.extends.top_left.x..=room.extends.bottom_right.x;
+        let y_range = room.extends.top_left.y..=room.extends.bottom_right.y;
+        for (x, y) in x_range.cartesian_product(y_range) {
+            if let Some(level) = light.get_mut(&Coordinate { x, y }) {
+                *level = ambient_light;
+            }
+        }
+    }
+
+    for (source, radius) in light_sources(world) {
+        for coordinate in fov::field_of_view(source, radius as i32, map, world, resources) {
+            let level = match source.distance(coordinate) <= radius as f64 * BRIGHT_PART_OF_RADIUS {
+                true => BRIGHT_LIGHT,
+                false => DIM_LIGHT,
+            };
+
+            let current = light.entry(coordinate).or_insert(DARK);
+            *current = (*current).max(level);
+        }
+    }
+
+    light
+}
+
+fn light_sources(world: &World) -> Vec<(Coordinate, u32)> {
+    let Some(positions) = world.borrow_component_vec::<Position>() else {
+        return Vec::new();
+    };
+
+    positions
+        .iter()
+        .enumerate()
+        .filter_map(|(entity, position)| {
+            let position = position.as_ref()?;
+            let radius = light_radius(entity, world)?;
+            Some((position.coordinate(), radius))
+        })
+        .collect()
+}
+
+fn light_radius(entity: usize, world: &World) -> Option<u32> {
+    let own_light = world
+        .borrow_entity_component::<LightSource>(entity)
+        .map(|light| light.radius);
+    let burning = world
+        .borrow_entity_component::<EffectHandler>(entity)
+        .filter(|effects| effects.has_effect(EffectType::Burning))
+        .map(|_| BURNING_LIGHT_RADIUS);
+
+    own_light.max(burning)
+}
+
+// How far away monsters notice someone standing in this light.
+pub fn spotting_range(light: u32) -> f64 {
+    match light {
+        DARK => DARK_SPOTTING_RANGE,
+        DIM_LIGHT => DIM_SPOTTING_RANGE,
+        _ => f64::INFINITY,
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{resources::ResourceManager, world::World};
+use crate::{component::Movement, resources::ResourceManager, world::World};
 
 use super::{utils::Coordinate, GameMap};
 
@@ -18,6 +18,73 @@ pub fn line_of_sight(
     !los_block_on_line(line_between, map, world, resources)
 }
 
+/* COVER
+    Obstructing tiles and entities, like rubble or other creatures, don't block sight but give cover
+    to whatever stands behind them. Ranged attacks and spells lose part of their damage to it.
+ */
+
+const PARTIAL_COVER_MULTIPLIER: f64 = 0.75;
+const HEAVY_COVER_MULTIPLIER: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Cover {
+    #[default]
+    None,
+    Partial,
+    Heavy,
+}
+
+impl Cover {
+    fn from_obstructions(obstructions: usize) -> Self {
+        match obstructions {
+            0 => Self::None,
+            1 => Self::Partial,
+            _ => Self::Heavy,
+        }
+    }
+
+    pub fn damage_multiplier(&self) -> f64 {
+        match self {
+            Self::None => 1.0,
+            Self::Partial => PARTIAL_COVER_MULTIPLIER,
+            Self::Heavy => HEAVY_COVER_MULTIPLIER,
+        }
+    }
+
+    pub fn describe(&self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Partial => Some("partial cover"),
+            Self::Heavy => Some("heavy cover"),
+        }
+    }
+}
+
+// Cover the destination has against the origin, from what obstructs the line between them.
+pub fn cover_on_line(
+    origin: Coordinate,
+    destination: Coordinate,
+    map: &GameMap,
+    world: &World,
+    resources: &ResourceManager,
+) -> Cover {
+    if origin == destination {
+        return Cover::None;
+    }
+
+    let full_line = linetrace(origin, destination);
+    let line_between = &full_line[1..full_line.len() - 1];
+    let obstructions = line_between
+        .iter()
+        .filter(|point| {
+            map.is_tile_sight_obstructing(**point, resources)
+                || world.get_sight_obstructing_entity(**point).is_some()
+        })
+        .count();
+
+    Cover::from_obstructions(obstructions)
+}
+
 fn los_block_on_line(
     line: &[Coordinate],
     map: &GameMap,
@@ -37,7 +104,7 @@ fn collision_on_line(
     resources: &ResourceManager,
 ) -> bool {
     line.iter().any(|point| {
-        !map.is_tile_walkable(*point, resources) || world.get_blocking_entity(*point).is_some()
+        !map.is_tile_passable(*point, Movement::FLYING, resources) || world.get_blocking_entity(*point).is_some()
     })
 }
 
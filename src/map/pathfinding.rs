@@ -3,6 +3,7 @@ use std::cmp::Reverse;
 use std::collections::HashMap;
 
 use crate::component::tags::{Door, Monster};
+use crate::component::Movement;
 use crate::resources::ResourceManager;
 use crate::world::World;
 
@@ -25,7 +26,7 @@ impl NodeData {
         }
     }
     fn get_comparable(&self) -> u32 {
-        self.distance + self.h_value
+        self.distance.saturating_add(self.h_value)
     }
 }
 
@@ -56,6 +57,7 @@ pub fn pathfind<F>(
     world: &World,
     resources: &ResourceManager,
     heuristic: F,
+    movement: Movement,
     ignore_units: bool,
     ignore_doors: bool,
     hazard_cost: u32,
@@ -82,6 +84,7 @@ where
         &destination,
         heuristic,
         return_early,
+        movement,
         ignore_units,
         ignore_doors,
         hazard_cost,
@@ -125,6 +128,7 @@ fn backtrace_path(
 fn get_passable_neighbors(
     neighbors: &[Coordinate],
     visited_coord: &Coordinate,
+    movement: Movement,
     ignore_units: bool,
     ignore_doors: bool,
     map: &GameMap,
@@ -135,7 +139,7 @@ fn get_passable_neighbors(
         .into_iter()
         .map(|dir| *visited_coord + *dir)
         .filter(|&coord| {
-            let walkable = map.is_tile_walkable(coord, resources);
+            let passable = map.is_tile_passable(coord, movement, resources);
             let no_blocking_entity = match world.get_blocking_entity(coord) {
                 Some(entity) => {
                     let mut can_ignore_entity = false;
@@ -150,7 +154,7 @@ fn get_passable_neighbors(
                 }
                 None => true,
             };
-            walkable && no_blocking_entity
+            passable && no_blocking_entity
         })
         .collect()
 }
@@ -163,6 +167,7 @@ fn fill_path_map<F>(
     destination: &Coordinate,
     heuristic: F,
     return_early: bool,
+    movement: Movement,
     ignore_units: bool,
     ignore_doors: bool,
     hazard_cost: u32,
@@ -185,6 +190,7 @@ where
         let passable_neighbors = get_passable_neighbors(
             neighbors,
             &visited_coord,
+            movement,
             ignore_units,
             ignore_doors,
             map,
@@ -197,11 +203,13 @@ where
             if closed.contains_key(&neighbor_coord) {
                 continue;
             }
-            let cost = match world.coordinate_has_hazard(neighbor_coord) {
+            let is_hazard = world.coordinate_has_hazard(neighbor_coord)
+                || map.is_tile_hazardous(neighbor_coord, movement, resources);
+            let cost = match is_hazard {
                 true => hazard_cost,
-                false => 1,
+                false => map.get_movement_cost(neighbor_coord, movement, resources),
             };
-            let distance_through_here = visited_data.distance + cost;
+            let distance_through_here = visited_data.distance.saturating_add(cost);
             // neighbor in open set already
             if let Some(Reverse(neigbor_data)) = open.get_priority(&neighbor_coord) {
                 if neigbor_data.distance > distance_through_here {
@@ -235,6 +243,7 @@ pub fn calculate_pathing_grid(
     map: &GameMap,
     world: &World,
     resources: &ResourceManager,
+    movement: Movement,
     ignore_units: bool,
     ignore_doors: bool,
     hazard_cost: u32,
@@ -260,6 +269,7 @@ pub fn calculate_pathing_grid(
         &destination,
         heuristic,
         return_early,
+        movement,
         ignore_units,
         ignore_doors,
         hazard_cost,
@@ -1,6 +1,9 @@
-use crate::{resources::id::RoomTemplateID, spawning::spawnentry::SpawnEntryType};
+use crate::{
+    resources::id::{RoomTemplateID, VaultID},
+    spawning::spawnentry::SpawnEntryType,
+};
 
-use super::{boxextends::BoxExtends, utils::Coordinate};
+use super::{boxextends::BoxExtends, shape::RoomShape, utils::Coordinate};
 
 #[derive(Debug, Default, Clone)]
 pub struct Room {
@@ -9,6 +12,8 @@ pub struct Room {
     pub template: Option<RoomTemplateID>,
     pub room_depth: Option<u32>,
     pub extra_spawn: Option<SpawnEntryType>,
+    pub vault: Option<VaultID>,
+    pub shape: RoomShape,
 }
 
 impl Room {
@@ -19,6 +24,8 @@ impl Room {
             template: None,
             room_depth: None,
             extra_spawn: None,
+            vault: None,
+            shape: RoomShape::Rectangle,
         }
     }
 }
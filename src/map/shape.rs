@@ -0,0 +1,182 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::rng::game_rng;
+use super::{
+    boxextends::BoxExtends,
+    utils::{Axis, Coordinate},
+};
+
+// Shapes decide which cells inside a room's box are floor. Everything else inside the box,
+// including the outer ring, is drawn as wall, so corridors always meet a solid edge.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoomShape {
+    #[default]
+    Rectangle,
+    Pillars,
+    Circle,
+    Cross,
+    LShape,
+    Nested,
+}
+
+// Relative weights for picking a shape, set per depth in generation.yaml.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoomShapeWeights {
+    pub rectangle: u32,
+    pub pillars: u32,
+    pub circle: u32,
+    pub cross: u32,
+    pub l_shape: u32,
+    pub nested: u32,
+}
+
+impl Default for RoomShapeWeights {
+    fn default() -> Self {
+        Self {
+            rectangle: 1,
+            pillars: 0,
+            circle: 0,
+            cross: 0,
+            l_shape: 0,
+            nested: 0,
+        }
+    }
+}
+
+impl RoomShapeWeights {
+    // Picks among the shapes that fit into the box, falling back to a plain rectangle.
+    pub fn pick_for(&self, extends: &BoxExtends) -> RoomShape {
+        let candidates: Vec<(RoomShape, u32)> = [
+            (RoomShape::Rectangle, self.rectangle),
+            (RoomShape::Pillars, self.pillars),
+            (RoomShape::Circle, self.circle),
+            (RoomShape::Cross, self.cross),
+            (RoomShape::LShape, self.l_shape),
+            (RoomShape::Nested, self.nested),
+        ]
+        .into_iter()
+        .filter(|(shape, weight)| *weight > 0 && shape.fits_in(extends))
+        .collect();
+
+        let total: u32 = candidates.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return RoomShape::Rectangle;
+        }
+
+        let mut roll = game_rng().gen_range(0..total);
+        for (shape, weight) in candidates {
+            if roll < weight {
+                return shape;
+            }
+            roll -= weight;
+        }
+
+        RoomShape::Rectangle
+    }
+}
+
+impl RoomShape {
+    // Smallest side length of the whole box, walls included.
+    fn min_side_length(&self) -> i32 {
+        match self {
+            Self::Rectangle => 3,
+            Self::LShape => 6,
+            Self::Pillars | Self::Circle | Self::Cross => 7,
+            Self::Nested => 9,
+        }
+    }
+
+    pub fn fits_in(&self, extends: &BoxExtends) -> bool {
+        let min_side_length = self.min_side_length();
+        extends.get_axis_size(Axis::Horizontal) >= min_side_length
+            && extends.get_axis_size(Axis::Vertical) >= min_side_length
+    }
+
+    pub fn floor_cells(&self, extends: &BoxExtends) -> HashSet<Coordinate> {
+        let (left, top) = (extends.top_left.x, extends.top_left.y);
+        let (right, bottom) = (extends.bottom_right.x, extends.bottom_right.y);
+        let center = extends.center();
+
+        let interior = (left + 1..right).flat_map(|x| (top + 1..bottom).map(move |y| Coordinate { x, y }));
+
+        match self {
+            Self::Rectangle => interior.collect(),
+            Self::Pillars => interior
+                .filter(|cell| {
+                    let is_pillar = left + 2 <= cell.x
+                        && cell.x <= right - 2
+                        && top + 2 <= cell.y
+                        && cell.y <= bottom - 2
+                        && (cell.x - left) % 2 == 0
+                        && (cell.y - top) % 2 == 0;
+                    !is_pillar
+                })
+                .collect(),
+            Self::Circle => {
+                let radius_x = (right - left - 1) as f64 / 2.0;
+                let radius_y = (bottom - top - 1) as f64 / 2.0;
+                let center_x = left as f64 + 0.5 + radius_x;
+                let center_y = top as f64 + 0.5 + radius_y;
+
+                interior
+                    .filter(|cell| {
+                        let delta_x = (cell.x as f64 + 0.5 - center_x) / radius_x;
+                        let delta_y = (cell.y as f64 + 0.5 - center_y) / radius_y;
+                        delta_x.powi(2) + delta_y.powi(2) <= 1.1
+                    })
+                    .collect()
+            }
+            Self::Cross => {
+                let band_x = ((right - left - 1) / 3).max(1);
+                let band_y = ((bottom - top - 1) / 3).max(1);
+
+                interior
+                    .filter(|cell| {
+                        (cell.x - center.x).abs() <= band_x / 2 + 1
+                            || (cell.y - center.y).abs() <= band_y / 2 + 1
+                    })
+                    .collect()
+            }
+            Self::LShape => {
+                // the missing corner follows from the position so it stays the same on every call
+                let corner = (left + top).rem_euclid(4);
+                interior
+                    .filter(|cell| {
+                        let is_left = cell.x < center.x;
+                        let is_top = cell.y < center.y;
+                        let is_right = cell.x > center.x;
+                        let is_bottom = cell.y > center.y;
+                        let is_missing = match corner {
+                            0 => is_left && is_top,
+                            1 => is_right && is_top,
+                            2 => is_right && is_bottom,
+                            _ => is_left && is_bottom,
+                        };
+                        !is_missing
+                    })
+                    .collect()
+            }
+            Self::Nested => {
+                // an inner room one tile in from the outer wall, open at the middle of its top side
+                let (inner_left, inner_top) = (left + 2, top + 2);
+                let (inner_right, inner_bottom) = (right - 2, bottom - 2);
+                let opening = Coordinate { x: center.x, y: inner_top };
+
+                interior
+                    .filter(|cell| {
+                        let on_inner_x = cell.x == inner_left || cell.x == inner_right;
+                        let on_inner_y = cell.y == inner_top || cell.y == inner_bottom;
+                        let within_x = inner_left <= cell.x && cell.x <= inner_right;
+                        let within_y = inner_top <= cell.y && cell.y <= inner_bottom;
+                        let is_inner_wall = (on_inner_x && within_y) || (on_inner_y && within_x);
+                        !is_inner_wall || *cell == opening
+                    })
+                    .collect()
+            }
+        }
+    }
+}
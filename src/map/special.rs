@@ -0,0 +1,186 @@
+use petgraph::Graph;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    component::{
+        behavior::{Behavior, BehaviorState},
+        tags::{Boss, StairsDown},
+    },
+    error::Result,
+    logger,
+    resources::{
+        id::SpawnableID, ResourceManager, DEFAULT_TILEID, FLOOR_TILEID, PLAYER_SPAWNABLE,
+        STAIRS_SPAWNABLE,
+    },
+    spawning::vault::{Vault, VaultGlyph},
+    world::World,
+};
+
+use super::{boxextends::BoxExtends, generation, room::Room, utils::Coordinate, GameMap};
+
+/* SPECIAL LEVELS
+    Some depths use a handcrafted level instead of a generated one, like a boss arena or a treasure floor.
+    The layout works like a vault layout, with two more markers: '@' is where the player arrives
+    and '>' where the way down appears, depending on the exit rule.
+
+    A special level is a single room, so the player sees all of it on arrival.
+    It isn't validated like generated levels, since it was drawn by hand.
+ */
+
+const ARRIVAL_MARKER: char = '@';
+const EXIT_MARKER: char = '>';
+
+// What happens when the player arrives.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum EntryRule {
+    // monsters keep the state they spawned in
+    #[default]
+    Quiet,
+    // every monster on the level wakes up
+    Alarmed,
+}
+
+// When the way down opens.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum ExitRule {
+    // the stairs are there from the start
+    #[default]
+    Stairs,
+    // the stairs appear once every spawn of this spawnable is dead
+    DefeatBoss(SpawnableID),
+}
+
+#[derive(Debug, Clone)]
+pub struct SpecialLevel {
+    pub name: String,
+    pub depth: u32,
+    pub layout: Vault,
+    pub entry: EntryRule,
+    pub exit: ExitRule,
+    pub arrival_message: Option<String>,
+}
+
+impl SpecialLevel {
+    fn markers(&self, marker: char) -> impl Iterator<Item = Coordinate> + '_ {
+        self.layout.layout.iter().enumerate().flat_map(move |(y, row)| {
+            row.chars()
+                .enumerate()
+                .filter(move |(_, character)| *character == marker)
+                .map(move |(x, _)| Coordinate { x: x as i32, y: y as i32 })
+        })
+    }
+
+    pub fn exit_locations(&self) -> Vec<Coordinate> {
+        self.markers(EXIT_MARKER).collect()
+    }
+}
+
+pub fn create_special_level(level: &SpecialLevel, resources: &ResourceManager) -> Result<(GameMap, World)> {
+    let (width, height) = (level.layout.width() as u32, level.layout.height() as u32);
+    if width < 3 || height < 3 {
+        return Err(format!("Special level {} is too small", level.name).into());
+    }
+
+    let mut map = GameMap::new(width, height);
+    map.depth = level.depth;
+    for x in 0..width as i32 {
+        for y in 0..height as i32 {
+            map.set_game_tile(Coordinate { x, y }, DEFAULT_TILEID);
+        }
+    }
+
+    for (coordinate, glyph) in level.layout.glyphs() {
+        let tile = match glyph {
+            VaultGlyph::Tile(tile) => tile,
+            VaultGlyph::Spawnable(_) => FLOOR_TILEID,
+        };
+        map.set_game_tile(coordinate, tile);
+    }
+
+    let markers = level.markers(ARRIVAL_MARKER).chain(level.markers(EXIT_MARKER));
+    for coordinate in markers.collect::<Vec<_>>() {
+        map.set_game_tile(coordinate, FLOOR_TILEID);
+    }
+
+    let bounds = BoxExtends {
+        top_left: Coordinate::default(),
+        bottom_right: Coordinate { x: width as i32 - 1, y: height as i32 - 1 },
+    };
+    let mut room = Room::new(bounds);
+    room.room_depth = Some(0);
+    map.room_graph = Graph::default();
+    map.room_graph.add_node(room);
+
+    let bsp = generation::binary_space_partitioning(width, height, 0);
+    let mut world = World::new_with(generation::entity_bsp_from_room_bsp(bsp));
+
+    let Some(arrival) = level.markers(ARRIVAL_MARKER).next() else {
+        return Err(format!("Special level {} has no arrival point", level.name).into());
+    };
+    spawn_at(PLAYER_SPAWNABLE, arrival, &map, &mut world, resources)?;
+
+    for (coordinate, glyph) in level.layout.glyphs() {
+        let VaultGlyph::Spawnable(spawnable) = glyph else {
+            continue;
+        };
+
+        let entity = spawn_at(spawnable, coordinate, &map, &mut world, resources)?;
+        if matches!(level.exit, ExitRule::DefeatBoss(boss) if boss == spawnable) {
+            world.add_component(entity, Boss)?;
+        }
+    }
+
+    if let ExitRule::Stairs = level.exit {
+        open_exits(level, &map, &mut world, resources)?;
+    }
+
+    Ok((map, world))
+}
+
+fn spawn_at(
+    spawnable: SpawnableID,
+    coordinate: Coordinate,
+    map: &GameMap,
+    world: &mut World,
+    resources: &ResourceManager,
+) -> Result<usize> {
+    let templates = resources.get_entity_templates(spawnable);
+    world.spawn_from_templates(&templates, map.depth, coordinate, resources)
+}
+
+// Applies the entry rule once the player has arrived.
+pub fn enter_special_level(level: &SpecialLevel, world: &mut World) {
+    if let Some(message) = &level.arrival_message {
+        logger::log_message(message);
+    }
+
+    if let EntryRule::Alarmed = level.entry {
+        let player_position = world.get_player_position().ok();
+        if let Some(behaviors) = world.borrow_component_vec_mut::<Behavior>() {
+            for behavior in behaviors.iter_mut().flatten() {
+                behavior.state = BehaviorState::Awake;
+                behavior.last_known_position = player_position;
+            }
+        }
+    }
+}
+
+pub fn open_exits(level: &SpecialLevel, map: &GameMap, world: &mut World, resources: &ResourceManager) -> Result<()> {
+    for coordinate in level.exit_locations() {
+        spawn_at(STAIRS_SPAWNABLE, coordinate, map, world, resources)?;
+    }
+    Ok(())
+}
+
+// The way down is open when stairs stand anywhere on the level.
+pub fn has_open_exit(world: &World) -> bool {
+    world
+        .borrow_component_vec::<StairsDown>()
+        .is_some_and(|stairs| stairs.iter().any(|tag| tag.is_some()))
+}
+
+pub fn is_boss_alive(world: &World) -> bool {
+    world
+        .borrow_component_vec::<Boss>()
+        .is_some_and(|bosses| bosses.iter().any(|tag| tag.is_some()))
+}
@@ -0,0 +1,271 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt::Write,
+};
+
+use crate::{
+    component::{
+        tags::{Artifact, StairsDown},
+        Position,
+    },
+    resources::{id::RoomTemplateID, ResourceManager, MAIN_BRANCH},
+    rng,
+    spawning::spawn_all_entities,
+    world::World,
+};
+
+use super::{
+    generation,
+    utils::{Coordinate, DOWN, LEFT, RIGHT, UP},
+    validation::{validate_level, LevelIssue},
+    GameMap,
+};
+
+/* GENERATION STATISTICS
+    Generates batches of levels without a window and sums up what came out, so changes to the generator
+    can be checked against numbers. Level number i of a batch is generated with seed + i.
+
+    An attempt fails the same way it does in Game::new: spawning returns an error or validation finds issues.
+    Corridors are the walkable tiles outside of every room, dead ends are rooms with a single neighbor.
+ */
+
+// Game::new gives up after this many attempts.
+const MAX_ATTEMPTS: u32 = 6;
+const ROOM_AREA_BUCKET: usize = 10;
+const STAIRS_DISTANCE_BUCKET: usize = 5;
+
+#[derive(Debug, Clone, Default)]
+pub struct DepthStatistics {
+    pub depth: u32,
+    pub levels: u32,
+    pub attempts: u32,
+    pub failed_attempts: u32,
+    // Levels where every attempt failed.
+    pub given_up: u32,
+    pub failure_reasons: BTreeMap<String, u32>,
+    pub room_counts: Vec<usize>,
+    pub room_areas: Vec<usize>,
+    pub dead_ends: Vec<usize>,
+    pub corridor_lengths: Vec<usize>,
+    pub templates: BTreeMap<RoomTemplateID, u32>,
+    // Walking distance from the player to the stairs, or to the artifact on the final floor.
+    pub stairs_distances: Vec<usize>,
+    pub unreachable_stairs: u32,
+}
+
+pub fn collect_statistics(depth: u32, count: u32, seed: u64, resources: &ResourceManager) -> DepthStatistics {
+    let mut statistics = DepthStatistics {
+        depth,
+        ..Default::default()
+    };
+
+    for level in 0..count {
+        rng::set_seed(seed.wrapping_add(level as u64));
+        statistics.levels += 1;
+
+        let mut finished = false;
+        for _ in 0..MAX_ATTEMPTS {
+            statistics.attempts += 1;
+
+            let (map, bsp) = generation::generate_new(depth, MAIN_BRANCH, resources);
+            let mut world = World::new_with(bsp);
+            if let Err(error) = spawn_all_entities(&map, &mut world, resources) {
+                statistics.failed_attempts += 1;
+                *statistics.failure_reasons.entry(format!("SpawnError: {error}")).or_default() += 1;
+                continue;
+            }
+
+            let issues = validate_level(&map, &world, resources);
+            if !issues.is_empty() {
+                statistics.failed_attempts += 1;
+                for issue in issues {
+                    *statistics.failure_reasons.entry(issue_kind(&issue)).or_default() += 1;
+                }
+                continue;
+            }
+
+            statistics.record_level(&map, &world, resources);
+            finished = true;
+            break;
+        }
+
+        if !finished {
+            statistics.given_up += 1;
+        }
+    }
+
+    statistics
+}
+
+// The variant name of the issue, without the coordinates that differ between levels.
+fn issue_kind(issue: &LevelIssue) -> String {
+    let debug = format!("{issue:?}");
+    debug
+        .split(|character: char| !character.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+impl DepthStatistics {
+    fn record_level(&mut self, map: &GameMap, world: &World, resources: &ResourceManager) {
+        let graph = &map.room_graph;
+        self.room_counts.push(graph.node_count());
+        self.dead_ends.push(
+            graph
+                .node_indices()
+                .filter(|index| graph.neighbors(*index).count() == 1)
+                .count(),
+        );
+
+        for room in graph.node_weights() {
+            self.room_areas.push(map.get_room_floor(&room.extends, resources).len());
+            if let Some(template) = room.template {
+                *self.templates.entry(template).or_default() += 1;
+            }
+        }
+
+        let corridor_length = map
+            .map
+            .keys()
+            .filter(|coordinate| map.is_tile_walkable(**coordinate, resources))
+            .filter(|coordinate| {
+                !graph.node_weights().any(|room| {
+                    let extends = &room.extends;
+                    extends.top_left.x <= coordinate.x
+                        && coordinate.x <= extends.bottom_right.x
+                        && extends.top_left.y <= coordinate.y
+                        && coordinate.y <= extends.bottom_right.y
+                })
+            })
+            .count();
+        self.corridor_lengths.push(corridor_length);
+
+        match stairs_distance(map, world, resources) {
+            Some(distance) => self.stairs_distances.push(distance),
+            None => self.unreachable_stairs += 1,
+        }
+    }
+
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        let successes = self.attempts - self.failed_attempts;
+
+        let _ = writeln!(report, "Depth {}: {} levels", self.depth, self.levels);
+        let _ = writeln!(
+            report,
+            "  attempts: {} ({} failed, {:.1}% failure rate), gave up on {} levels",
+            self.attempts,
+            self.failed_attempts,
+            percentage(self.failed_attempts as usize, self.attempts as usize),
+            self.given_up
+        );
+        for (reason, amount) in &self.failure_reasons {
+            let _ = writeln!(report, "    {reason}: {amount}");
+        }
+
+        let _ = writeln!(report, "  rooms per level: {}", summarize(&self.room_counts));
+        let _ = writeln!(report, "  room floor tiles: {}", summarize(&self.room_areas));
+        report.push_str(&histogram(&self.room_areas, ROOM_AREA_BUCKET));
+        let _ = writeln!(report, "  dead ends per level: {}", summarize(&self.dead_ends));
+        let _ = writeln!(report, "  corridor tiles per level: {}", summarize(&self.corridor_lengths));
+
+        let _ = writeln!(report, "  stairs distance: {}", summarize(&self.stairs_distances));
+        report.push_str(&histogram(&self.stairs_distances, STAIRS_DISTANCE_BUCKET));
+        if self.unreachable_stairs > 0 {
+            let _ = writeln!(report, "    unreachable: {}", self.unreachable_stairs);
+        }
+
+        let _ = writeln!(report, "  room templates in {successes} levels:");
+        let total_rooms: u32 = self.templates.values().sum();
+        for (template, amount) in &self.templates {
+            let _ = writeln!(
+                report,
+                "    {:>3}: {amount} ({:.1}%)",
+                template.0,
+                percentage(*amount as usize, total_rooms as usize)
+            );
+        }
+
+        report
+    }
+}
+
+// Breadth first walk from the player to the closest objective, counting steps.
+fn stairs_distance(map: &GameMap, world: &World, resources: &ResourceManager) -> Option<usize> {
+    let start = world.get_player_position().ok()?;
+    let positions = world.borrow_component_vec::<Position>()?;
+    let objectives: Vec<Coordinate> = positions
+        .iter()
+        .enumerate()
+        .filter(|(entity, _)| {
+            world.borrow_entity_component::<StairsDown>(*entity).is_some()
+                || world.borrow_entity_component::<Artifact>(*entity).is_some()
+        })
+        .filter_map(|(_, position)| position.map(|position| position.coordinate()))
+        .collect();
+
+    let mut distances = HashMap::from([(start, 0)]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(coordinate) = queue.pop_front() {
+        let distance = distances[&coordinate];
+        if objectives.contains(&coordinate) {
+            return Some(distance);
+        }
+
+        for direction in [UP, DOWN, LEFT, RIGHT] {
+            let neighbor = coordinate + direction;
+            if !distances.contains_key(&neighbor) && map.is_tile_walkable(neighbor, resources) {
+                distances.insert(neighbor, distance + 1);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    None
+}
+
+fn percentage(part: usize, total: usize) -> f64 {
+    match total {
+        0 => 0.0,
+        _ => part as f64 * 100.0 / total as f64,
+    }
+}
+
+fn summarize(values: &[usize]) -> String {
+    if values.is_empty() {
+        return "none".to_string();
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort();
+    let mean = sorted.iter().sum::<usize>() as f64 / sorted.len() as f64;
+    format!(
+        "min {}, median {}, mean {:.1}, max {}",
+        sorted[0],
+        sorted[sorted.len() / 2],
+        mean,
+        sorted[sorted.len() - 1]
+    )
+}
+
+fn histogram(values: &[usize], bucket_size: usize) -> String {
+    let mut buckets: BTreeMap<usize, usize> = BTreeMap::new();
+    for value in values {
+        *buckets.entry(value / bucket_size).or_default() += 1;
+    }
+
+    let mut histogram = String::new();
+    for (bucket, amount) in buckets {
+        let low = bucket * bucket_size;
+        let _ = writeln!(
+            histogram,
+            "    {:>3}-{:<3} {:>5} {}",
+            low,
+            low + bucket_size - 1,
+            amount,
+            "#".repeat((percentage(amount, values.len()) / 2.0).round() as usize)
+        );
+    }
+    histogram
+}
@@ -0,0 +1,94 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::rng::game_rng;
+use crate::resources::{
+    id::{RoomTemplateID, SpawnEntryID, SpawnableID, TileID},
+    ResourceManager, FLOOR_TILEID, WALL_TILEID,
+};
+
+use super::GameMap;
+
+/* THEMES
+    A theme gives the floors of a depth range their own look and inhabitants, like a crypt or flooded halls.
+
+    Generation itself only draws plain floor and wall, which the corridor and repair steps rely on.
+    Once a level is finished, its tiles are painted over with the theme's variants.
+    The theme also narrows down the room templates and adds decorations and ambient spawns to every room.
+ */
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TileVariant {
+    pub tile: TileID,
+    pub weight: u32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Decoration {
+    pub spawnable: SpawnableID,
+    // Chance for every free floor tile of a room.
+    pub chance: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Theme {
+    pub name: String,
+    pub min_depth: u32,
+    pub max_depth: Option<u32>,
+    pub floor_tiles: Vec<TileVariant>,
+    pub wall_tiles: Vec<TileVariant>,
+    pub decorations: Vec<Decoration>,
+    pub ambient_spawns: Vec<SpawnEntryID>,
+    // Templates rooms can pick from. Every template is allowed when empty.
+    pub room_templates: Vec<RoomTemplateID>,
+    // Only used by branches that name it, never picked for the main line.
+    pub branch_only: bool,
+}
+
+impl Theme {
+    pub fn fits_depth(&self, depth: u32) -> bool {
+        self.min_depth <= depth && self.max_depth.map_or(true, |max_depth| depth <= max_depth)
+    }
+
+    pub fn allows_template(&self, template: RoomTemplateID) -> bool {
+        self.room_templates.is_empty() || self.room_templates.contains(&template)
+    }
+}
+
+// Replaces plain floor and wall with the variants of the map's theme.
+pub fn paint_theme(map: &mut GameMap, resources: &ResourceManager) {
+    let Some(theme) = map.theme.and_then(|theme| resources.get_theme(theme)) else {
+        return;
+    };
+
+    let mut coordinates: Vec<_> = map.map.keys().copied().collect();
+    coordinates.sort();
+    for coordinate in coordinates {
+        let variants = match map.get_game_tile(coordinate) {
+            FLOOR_TILEID => &theme.floor_tiles,
+            WALL_TILEID => &theme.wall_tiles,
+            _ => continue,
+        };
+
+        if let Some(tile) = pick_variant(variants) {
+            map.set_game_tile(coordinate, tile);
+        }
+    }
+}
+
+fn pick_variant(variants: &[TileVariant]) -> Option<TileID> {
+    let total: u32 = variants.iter().map(|variant| variant.weight).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut roll = game_rng().gen_range(0..total);
+    for variant in variants {
+        if roll < variant.weight {
+            return Some(variant.tile);
+        }
+        roll -= variant.weight;
+    }
+
+    None
+}
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
-use crate::resources::{self, id::ImageID};
+use crate::component::{combat::DamageType, effect::Effect};
+use crate::resources::{self, id::{ImageID, TileID}};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Passable {
@@ -23,6 +24,34 @@ pub struct GameTile {
     pub image: ImageID,
     pub passable: Passable,
     pub los: Los,
+    // Turns it takes to step onto the tile.
+    pub movement_cost: u32,
+    // Dealt every turn to anything standing on the tile that doesn't fly.
+    pub damage: u32,
+    // Applied when stepping onto the tile.
+    pub status_effect: Option<Effect>,
+    pub flammable: bool,
+    pub diggable: bool,
+    // Magnitude of the noise made when stepping onto the tile.
+    pub noise: u32,
+    // Damage the tile takes before it breaks. Tiles without health can't be destroyed.
+    pub health: Option<u32>,
+    // What the tile turns into once broken or dug out, floor if not set.
+    pub broken_into: Option<TileID>,
+}
+
+impl GameTile {
+    pub fn is_hazardous(&self) -> bool {
+        self.damage > 0 || self.status_effect.is_some()
+    }
+
+    // Tiles that set units on fire burn them, everything else hurts like a blow.
+    pub fn damage_type(&self) -> DamageType {
+        match self.status_effect {
+            Some(effect) => effect.effect_type.damage_type(),
+            None => DamageType::Physical,
+        }
+    }
 }
 
 impl Default for GameTile {
@@ -32,6 +61,14 @@ impl Default for GameTile {
             image: resources::DEFAULT_IMAGE_ID,
             passable: Passable::None,
             los: Los::Clear,
+            movement_cost: 1,
+            damage: 0,
+            status_effect: None,
+            flammable: false,
+            diggable: false,
+            noise: 0,
+            health: None,
+            broken_into: None,
         }
     }
 }
@@ -1,9 +1,10 @@
 use itertools::Itertools;
 use log::debug;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::{collections::{HashSet, VecDeque}, ops::Range, hash::Hash, fmt::Debug};
 
+use crate::rng::game_rng;
 use crate::{resources::ResourceManager, world::World};
 
 use super::GameMap;
@@ -47,8 +48,8 @@ impl Coordinate {
 
     pub fn random(x_range: &Range<i32>, y_range: &Range<i32>) -> Self {
         Self {
-            x: thread_rng().gen_range(x_range.clone()),
-            y: thread_rng().gen_range(y_range.clone()),
+            x: game_rng().gen_range(x_range.clone()),
+            y: game_rng().gen_range(y_range.clone()),
         }
     }
 
@@ -0,0 +1,283 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{
+    component::{
+        tags::{Artifact, Door, Exit, Player, StairsDown},
+        Collision, Position,
+    },
+    resources::ResourceManager,
+    world::World,
+};
+
+use super::{boxextends::BoxExtends, generation, tile::Passable, utils::Coordinate, GameMap};
+
+/* LEVEL VALIDATION
+    Checks a generated and populated level for anything that would make it unplayable or unfair.
+    The game generates the level again when any issue is found, tooling can list them instead.
+
+    Reachability only looks at tiles. Doors count as passable since they can be opened.
+ */
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LevelIssue {
+    NoPlayer,
+    // No stairs down, or on the final floor no artifact or exit.
+    MissingObjective,
+    UnreachableObjective(Coordinate),
+    UnreachableRoom(BoxExtends),
+    MisplacedDoor(Coordinate),
+    EntityInWall { entity: usize, coordinate: Coordinate },
+    OverlappingEntities { entities: Vec<usize>, coordinate: Coordinate },
+    TooManySpawns { room: BoxExtends, count: usize },
+}
+
+pub fn validate_level(map: &GameMap, world: &World, resources: &ResourceManager) -> Vec<LevelIssue> {
+    let positions: Vec<(usize, Coordinate)> = match world.borrow_component_vec::<Position>() {
+        Some(positions) => positions
+            .iter()
+            .enumerate()
+            .filter_map(|(entity, position)| position.map(|position| (entity, position.coordinate())))
+            .collect(),
+        None => vec![],
+    };
+
+    let mut issues = vec![];
+
+    match world.get_player_position() {
+        Ok(start) => {
+            let region = map.get_walkable_region(start, resources);
+            issues.extend(check_objectives(&region, &positions, map, world, resources));
+            issues.extend(check_rooms(&region, map, resources));
+        }
+        Err(_) => issues.push(LevelIssue::NoPlayer),
+    }
+
+    issues.extend(check_doors(&positions, map, world, resources));
+    issues.extend(check_placement(&positions, map, world, resources));
+    issues.extend(check_spawn_counts(&positions, map, world, resources));
+    issues
+}
+
+fn check_objectives(
+    region: &HashSet<Coordinate>,
+    positions: &[(usize, Coordinate)],
+    map: &GameMap,
+    world: &World,
+    resources: &ResourceManager,
+) -> Vec<LevelIssue> {
+    let has = |entity: usize| match resources.is_final_depth(map.depth) {
+        true => {
+            world.borrow_entity_component::<Artifact>(entity).is_some()
+                || world.borrow_entity_component::<Exit>(entity).is_some()
+        }
+        false => world.borrow_entity_component::<StairsDown>(entity).is_some(),
+    };
+
+    let objectives: Vec<Coordinate> = positions
+        .iter()
+        .filter(|(entity, _)| world.borrow_entity_component::<Player>(*entity).is_none())
+        .filter(|(entity, _)| has(*entity))
+        .map(|(_, coordinate)| *coordinate)
+        .collect();
+
+    if objectives.is_empty() {
+        return vec![LevelIssue::MissingObjective];
+    }
+
+    objectives
+        .into_iter()
+        .filter(|coordinate| !region.contains(coordinate))
+        .map(LevelIssue::UnreachableObjective)
+        .collect()
+}
+
+fn check_rooms(region: &HashSet<Coordinate>, map: &GameMap, resources: &ResourceManager) -> Vec<LevelIssue> {
+    map.room_graph
+        .node_weights()
+        .filter(|room| {
+            !map.get_room_floor(&room.extends, resources)
+                .iter()
+                .any(|coordinate| region.contains(coordinate))
+        })
+        .map(|room| LevelIssue::UnreachableRoom(room.extends))
+        .collect()
+}
+
+fn check_doors(
+    positions: &[(usize, Coordinate)],
+    map: &GameMap,
+    world: &World,
+    resources: &ResourceManager,
+) -> Vec<LevelIssue> {
+    positions
+        .iter()
+        .filter(|(entity, _)| world.borrow_entity_component::<Door>(*entity).is_some())
+        .filter(|(_, coordinate)| !generation::check_door_conditions(*coordinate, map, resources))
+        .map(|(_, coordinate)| LevelIssue::MisplacedDoor(*coordinate))
+        .collect()
+}
+
+// Entities stand on walkable tiles, and never share a tile with something blocking.
+fn check_placement(
+    positions: &[(usize, Coordinate)],
+    map: &GameMap,
+    world: &World,
+    resources: &ResourceManager,
+) -> Vec<LevelIssue> {
+    let mut issues: Vec<LevelIssue> = positions
+        .iter()
+        .filter(|(_, coordinate)| !map.is_tile_walkable(*coordinate, resources))
+        .map(|(entity, coordinate)| LevelIssue::EntityInWall {
+            entity: *entity,
+            coordinate: *coordinate,
+        })
+        .collect();
+
+    let mut occupants: BTreeMap<Coordinate, Vec<usize>> = BTreeMap::new();
+    for (entity, coordinate) in positions {
+        occupants.entry(*coordinate).or_default().push(*entity);
+    }
+
+    let is_blocking = |entity: &usize| {
+        matches!(
+            world.borrow_entity_component::<Collision>(*entity),
+            Some(Collision(Passable::None))
+        )
+    };
+
+    let overlaps = occupants
+        .into_iter()
+        .filter(|(_, entities)| entities.len() > 1 && entities.iter().any(is_blocking))
+        .map(|(coordinate, entities)| LevelIssue::OverlappingEntities { entities, coordinate });
+
+    issues.extend(overlaps);
+    issues
+}
+
+fn check_spawn_counts(
+    positions: &[(usize, Coordinate)],
+    map: &GameMap,
+    world: &World,
+    resources: &ResourceManager,
+) -> Vec<LevelIssue> {
+    let max_spawns = resources.get_generation_parameters(map.depth).max_spawns_per_room;
+
+    map.room_graph
+        .node_weights()
+        .filter_map(|room| {
+            let floor: HashSet<Coordinate> = map.get_room_floor(&room.extends, resources).into_iter().collect();
+            let count = positions
+                .iter()
+                .filter(|(_, coordinate)| floor.contains(coordinate))
+                .filter(|(entity, _)| world.borrow_entity_component::<Door>(*entity).is_none())
+                .filter(|(entity, _)| world.borrow_entity_component::<Player>(*entity).is_none())
+                .count();
+
+            (count > max_spawns).then_some(LevelIssue::TooManySpawns {
+                room: room.extends,
+                count,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        component::tags::Door,
+        map::utils::{DOWN, LEFT, RIGHT, UP},
+        resources::{FLOOR_TILEID, MAIN_BRANCH, WALL_TILEID},
+        rng,
+        spawning::spawn_all_entities,
+    };
+
+    const SEED: u64 = 1;
+    const DEPTH: u32 = 2;
+
+    fn generate_level(resources: &ResourceManager) -> (GameMap, World) {
+        rng::set_seed(SEED);
+        let (map, bsp) = generation::generate_new(DEPTH, MAIN_BRANCH, resources);
+        let mut world = World::new_with(bsp);
+        spawn_all_entities(&map, &mut world, resources).expect("the seeded level spawns");
+        (map, world)
+    }
+
+    fn positioned_entities(world: &World) -> Vec<(usize, Coordinate)> {
+        world
+            .borrow_component_vec::<Position>()
+            .expect("the level has positions")
+            .iter()
+            .enumerate()
+            .filter_map(|(entity, position)| position.map(|position| (entity, position.coordinate())))
+            .collect()
+    }
+
+    #[test]
+    fn generated_level_is_valid() {
+        let resources = ResourceManager::new().unwrap();
+        let (map, world) = generate_level(&resources);
+
+        assert_eq!(validate_level(&map, &world, &resources), vec![]);
+    }
+
+    #[test]
+    fn walled_off_room_is_unreachable() {
+        let resources = ResourceManager::new().unwrap();
+        let (mut map, world) = generate_level(&resources);
+        let start = world.get_player_position().unwrap();
+
+        let room = map
+            .room_graph
+            .node_weights()
+            .find(|room| !room.extends.contains_point(start))
+            .cloned()
+            .expect("the level has more than one room");
+        let floor = room.shape.floor_cells(&room.extends);
+        let (top_left, bottom_right) = (room.extends.top_left, room.extends.bottom_right);
+        for x in top_left.x..=bottom_right.x {
+            for y in top_left.y..=bottom_right.y {
+                let coordinate = Coordinate { x, y };
+                if !floor.contains(&coordinate) {
+                    map.set_game_tile(coordinate, WALL_TILEID);
+                }
+            }
+        }
+
+        let issues = validate_level(&map, &world, &resources);
+        assert!(issues.contains(&LevelIssue::UnreachableRoom(room.extends)));
+    }
+
+    #[test]
+    fn door_outside_a_wall_gap_is_misplaced() {
+        let resources = ResourceManager::new().unwrap();
+        let (mut map, world) = generate_level(&resources);
+
+        let (_, door) = positioned_entities(&world)
+            .into_iter()
+            .find(|(entity, _)| world.borrow_entity_component::<Door>(*entity).is_some())
+            .expect("the level has a door");
+        for direction in [UP, DOWN, LEFT, RIGHT] {
+            map.set_game_tile(door + direction, FLOOR_TILEID);
+        }
+
+        let issues = validate_level(&map, &world, &resources);
+        assert!(issues.contains(&LevelIssue::MisplacedDoor(door)));
+    }
+
+    #[test]
+    fn entity_on_a_wall_is_reported() {
+        let resources = ResourceManager::new().unwrap();
+        let (mut map, world) = generate_level(&resources);
+        let player = world.get_player_id().unwrap();
+
+        let (entity, coordinate) = positioned_entities(&world)
+            .into_iter()
+            .find(|(entity, _)| *entity != player)
+            .expect("the level has entities besides the player");
+        map.set_game_tile(coordinate, WALL_TILEID);
+
+        let issues = validate_level(&map, &world, &resources);
+        assert!(issues.contains(&LevelIssue::EntityInWall { entity, coordinate }));
+    }
+}
@@ -0,0 +1,314 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    component::{
+        attributes::{Attributes, Xp},
+        items::Coins,
+        spell::Spellbook,
+        stats::RunStats,
+        tags::{Artifact, Door, Exit, Monster, Player, StairsDown},
+        Name,
+    },
+    error::Result,
+    logger,
+    map::{tile::{Los, Passable}, utils::Coordinate, GameMap},
+    resources::ResourceManager,
+    world::World,
+};
+
+/* MORGUE FILES
+    A morgue is the character dump written when a run ends. It is saved twice:
+    once as plain text meant for sharing, and once as yaml for tooling that reviews runs.
+
+    Map legend:
+        @ player, > stairs, < exit, & artifact, + door, lowercase letter: monster (first letter of its name),
+        * any other entity, # wall, . floor, ^ dangerous floor, ~ water, : chasm,
+        blank: unexplored.
+ */
+
+const MORGUE_PATH: &'static str = "morgue";
+const MORGUE_MESSAGE_COUNT: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RunOutcome {
+    Died,
+    Victory,
+    // Restarted before the run was over.
+    Abandoned,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpellUsage {
+    pub name: String,
+    pub times_cast: u32,
+    pub casts_left: u32,
+    pub casts_max: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Morgue {
+    pub name: String,
+    pub level: u32,
+    pub might: u32,
+    pub wit: u32,
+    pub skill: u32,
+    pub outcome: RunOutcome,
+    pub cause: String,
+    pub depth: u32,
+    pub turns: u32,
+    pub gold: u32,
+    pub xp: u32,
+    pub damage_dealt: u32,
+    pub damage_taken: u32,
+    pub doors_opened: u32,
+    pub tiles_explored: u32,
+    pub kills: Vec<(String, u32)>,
+    pub spells: Vec<SpellUsage>,
+    pub messages: Vec<String>,
+    pub map: Vec<String>,
+}
+
+impl Morgue {
+    pub fn new(
+        outcome: RunOutcome,
+        map: &GameMap,
+        world: &World,
+        resources: &ResourceManager,
+    ) -> Result<Self> {
+        let player = world.get_player_id()?;
+
+        let name = match world.borrow_entity_component::<Name>(player) {
+            Some(Name(name)) => name.clone(),
+            None => "Someone".to_string(),
+        };
+
+        let (level, xp) = match world.borrow_entity_component::<Xp>(player) {
+            Some(xp) => (xp.level, xp.current),
+            None => (0, 0),
+        };
+
+        let (might, wit, skill) = match world.borrow_entity_component::<Attributes>(player) {
+            Some(attributes) => (attributes.might, attributes.wit, attributes.skill),
+            None => (0, 0, 0),
+        };
+
+        let gold = match world.borrow_entity_component::<Coins>(player) {
+            Some(Coins(amount)) => *amount,
+            None => 0,
+        };
+
+        let stats = world
+            .borrow_entity_component::<RunStats>(player)
+            .cloned()
+            .unwrap_or_default();
+
+        let cause = match (outcome, &stats.last_hit_by) {
+            (RunOutcome::Died, Some(name)) => format!("Killed by {name}"),
+            (RunOutcome::Died, None) => "Died".to_string(),
+            (RunOutcome::Victory, _) => "Escaped with the artifact".to_string(),
+            (RunOutcome::Abandoned, _) => "Abandoned the run".to_string(),
+        };
+
+        let mut kills: Vec<(String, u32)> = stats.kills.clone().into_iter().collect();
+        kills.sort_by(|(a_name, a_count), (b_name, b_count)| {
+            b_count.cmp(a_count).then(a_name.cmp(b_name))
+        });
+
+        let spells = match world.borrow_entity_component::<Spellbook>(player) {
+            Some(spellbook) => spellbook
+                .get_spells()
+                .into_iter()
+                .map(|spell| SpellUsage {
+                    name: spell.name.0.clone(),
+                    times_cast: stats.spells_cast.get(&spell.name.0).copied().unwrap_or(0),
+                    casts_left: spell.casts_left,
+                    casts_max: spell.casts_max,
+                })
+                .collect(),
+            None => vec![],
+        };
+
+        Ok(Self {
+            name,
+            level,
+            might,
+            wit,
+            skill,
+            outcome,
+            cause,
+            depth: map.depth,
+            turns: stats.turns,
+            gold,
+            xp,
+            damage_dealt: stats.total_damage_dealt(),
+            damage_taken: stats.total_damage_taken(),
+            doors_opened: stats.doors_opened,
+            tiles_explored: stats.total_tiles_explored(),
+            kills,
+            spells,
+            messages: logger::recent_messages(MORGUE_MESSAGE_COUNT),
+            map: ascii_snapshot(map, world, resources),
+        })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut lines = Vec::new();
+
+        lines.push("RetroDungeon character dump".to_string());
+        lines.push(String::new());
+        lines.push(format!("{}, level {}", self.name, self.level));
+
+        lines.push(format!(
+            "{} on depth {} after {} turns.",
+            self.cause, self.depth, self.turns
+        ));
+        lines.push(String::new());
+
+        lines.push(format!(
+            "Might {}   Wit {}   Skill {}",
+            self.might, self.wit, self.skill
+        ));
+        lines.push(format!("Gold: {}   Xp: {}", self.gold, self.xp));
+        lines.push(format!(
+            "Damage dealt: {}   Damage taken: {}",
+            self.damage_dealt, self.damage_taken
+        ));
+        lines.push(format!(
+            "Doors opened: {}   Tiles explored: {}",
+            self.doors_opened, self.tiles_explored
+        ));
+        lines.push(String::new());
+
+        let total_kills: u32 = self.kills.iter().map(|(_, count)| count).sum();
+        lines.push(format!("Kills ({total_kills}):"));
+        for (name, count) in &self.kills {
+            lines.push(format!("  {name:<16}{count:>4}"));
+        }
+        lines.push(String::new());
+
+        lines.push("Spells:".to_string());
+        for spell in &self.spells {
+            lines.push(format!(
+                "  {:<16}cast {:>3} times, {}/{} left on final floor",
+                spell.name, spell.times_cast, spell.casts_left, spell.casts_max
+            ));
+        }
+        lines.push(String::new());
+
+        lines.push("Last messages:".to_string());
+        for message in &self.messages {
+            lines.push(format!("  {message}"));
+        }
+        lines.push(String::new());
+
+        lines.push("Final floor:".to_string());
+        lines.extend(self.map.iter().cloned());
+
+        lines.join("\n")
+    }
+
+    // Writes both the text and the yaml dump, returns the path of the text file.
+    pub fn save(&self) -> Result<PathBuf> {
+        self.save_to(Path::new(MORGUE_PATH))
+    }
+
+    pub fn save_to(&self, directory: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(directory)?;
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        let file_stem = format!("{}-{timestamp}", self.name.to_lowercase().replace(' ', "_"));
+
+        let text_path = directory.join(format!("{file_stem}.txt"));
+        let mut writer = BufWriter::new(File::create(&text_path)?);
+        writer.write_all(self.to_text().as_bytes())?;
+
+        let yaml_path = directory.join(format!("{file_stem}.yaml"));
+        let writer = BufWriter::new(File::create(yaml_path)?);
+        serde_yaml::to_writer(writer, self)?;
+
+        Ok(text_path)
+    }
+}
+
+pub fn ascii_snapshot(map: &GameMap, world: &World, resources: &ResourceManager) -> Vec<String> {
+    (0..map.height as i32)
+        .map(|y| {
+            (0..map.width as i32)
+                .map(|x| {
+                    let coordinate = Coordinate { x, y };
+                    if !map.is_tile_explored(coordinate) {
+                        return ' ';
+                    }
+                    entity_glyph(coordinate, world).unwrap_or_else(|| tile_glyph(coordinate, map, resources))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn tile_glyph(coordinate: Coordinate, map: &GameMap, resources: &ResourceManager) -> char {
+    let Some(tile) = resources.get_tile(map.get_game_tile(coordinate)) else {
+        return ' ';
+    };
+
+    match (tile.passable, tile.los) {
+        (Passable::Walk, _) if tile.is_hazardous() => '^',
+        (Passable::Walk, _) => '.',
+        (Passable::Swim, _) => '~',
+        (Passable::Fly, _) => ':',
+        (_, Los::Block) => '#',
+        _ => ' ',
+    }
+}
+
+fn entity_glyph(coordinate: Coordinate, world: &World) -> Option<char> {
+    // Lower value wins when several entities share a tile.
+    world
+        .get_entities_at_coordinate(coordinate)
+        .into_iter()
+        .map(|entity| glyph_of(entity, world))
+        .min_by_key(|(priority, _)| *priority)
+        .map(|(_, glyph)| glyph)
+}
+
+fn glyph_of(entity: usize, world: &World) -> (u32, char) {
+    if world.borrow_entity_component::<Player>(entity).is_some() {
+        return (0, '@');
+    }
+
+    if world.borrow_entity_component::<Monster>(entity).is_some() {
+        let initial = match world.borrow_entity_component::<Name>(entity) {
+            Some(Name(name)) => name.chars().next().unwrap_or('m'),
+            None => 'm',
+        };
+        return (1, initial.to_ascii_lowercase());
+    }
+
+    if world.borrow_entity_component::<StairsDown>(entity).is_some() {
+        return (2, '>');
+    }
+
+    if world.borrow_entity_component::<Exit>(entity).is_some() {
+        return (2, '<');
+    }
+
+    if world.borrow_entity_component::<Artifact>(entity).is_some() {
+        return (2, '&');
+    }
+
+    if world.borrow_entity_component::<Door>(entity).is_some() {
+        return (3, '+');
+    }
+
+    (4, '*')
+}
@@ -1,39 +1,57 @@
 use std::{collections::HashMap, path::Path};
 
 use id::*;
+use itertools::Itertools;
 use log::info;
+use rand::Rng;
+use serialize_dungeon::{Branch, DungeonDefinition};
+use serialize_generation::GenerationDefinition;
 use serialize_spell::SpellDefinition;
 
+use crate::rng::game_rng;
 use crate::component::image::ImageState;
 use crate::component::spell::SpellEffectName;
 use crate::component::Name;
 use crate::error::Result;
 use crate::system::TimeSlot;
 use crate::{
-    map::tile::GameTile,
+    map::{generation::GenerationParameters, special::SpecialLevel, theme::Theme, tile::GameTile},
     spawning::{
         entitytemplate::EntityTemplateEnum, roomtemplate::RoomTemplate, spawnentry::SpawnEntryType,
+        vault::Vault,
     },
 };
 
 pub mod id;
+pub mod serialize_dungeon;
 pub mod serialize_gametile;
+pub mod serialize_generation;
 pub mod serialize_roomtemplate;
 pub mod serialize_spawnable;
 pub mod serialize_spawnentry;
+pub mod serialize_special;
 pub mod serialize_spell;
+pub mod serialize_theme;
+pub mod serialize_vault;
 
 pub const DEFAULT_IMAGE_ID: ImageID = ImageID(4);
 
+pub const MAIN_BRANCH: BranchID = BranchID(0);
+
 pub const DEFAULT_TILEID: TileID = TileID(0);
 pub const WALL_TILEID: TileID = TileID(2);
 pub const FLOOR_TILEID: TileID = TileID(1);
 
 pub const DOOR_SPAWNABLE: SpawnableID = SpawnableID(0);
+pub const STAIRS_SPAWNABLE: SpawnableID = SpawnableID(3);
+pub const PLAYER_SPAWNABLE: SpawnableID = SpawnableID(4);
 pub const GOLD_PILE_SPAWNABLE: SpawnableID = SpawnableID(5);
+pub const SECRET_DOOR_SPAWNABLE: SpawnableID = SpawnableID(15);
 
 pub const PLAYER_SPAWNENTRY: SpawnEntryID = SpawnEntryID(0);
 pub const STAIRS_SPAWNENTRY: SpawnEntryID = SpawnEntryID(1);
+pub const EXIT_SPAWNENTRY: SpawnEntryID = SpawnEntryID(2);
+pub const ARTIFACT_SPAWNENTRY: SpawnEntryID = SpawnEntryID(5);
 
 
 pub const TILESET_SIZE: f32 = 32.0;
@@ -42,6 +60,11 @@ const TILES_PATH: &'static str = "data/tiles.yaml";
 const SPAWNABLES_PATH: &'static str = "data/spawnables.yaml";
 const SPAWN_ENTRY_PATH: &'static str = "data/spawnentries.yaml";
 const ROOM_TEMPLATE_PATH: &'static str = "data/roomtemplates.yaml";
+const VAULT_PATH: &'static str = "data/vaults.yaml";
+const DUNGEON_PATH: &'static str = "data/dungeon.yaml";
+const GENERATION_PATH: &'static str = "data/generation.yaml";
+const THEME_PATH: &'static str = "data/themes.yaml";
+const SPECIAL_LEVEL_PATH: &'static str = "data/speciallevels.yaml";
 
 #[derive(Default)]
 pub struct ResourceManager {
@@ -49,7 +72,12 @@ pub struct ResourceManager {
     spawnable_definitions: HashMap<SpawnableID, Vec<EntityTemplateEnum>>,
     spawn_entry_definitions: HashMap<SpawnEntryID, SpawnEntryType>,
     room_template_definitions: HashMap<RoomTemplateID, RoomTemplate>,
+    vault_definitions: HashMap<VaultID, Vault>,
+    theme_definitions: HashMap<ThemeID, Theme>,
+    special_levels: HashMap<u32, SpecialLevel>,
     spell_definition: HashMap<SpellDefinitionId, SpellDefinition>,
+    dungeon_definition: DungeonDefinition,
+    generation_definition: GenerationDefinition,
 }
 
 impl ResourceManager {
@@ -68,6 +96,21 @@ impl ResourceManager {
         info!("Reading room templates");
         resources.load_room_template_definitions()?;
 
+        info!("Reading vaults");
+        resources.load_vault_definitions()?;
+
+        info!("Reading themes");
+        resources.load_theme_definitions()?;
+
+        info!("Reading special levels");
+        resources.load_special_levels()?;
+
+        info!("Reading dungeon definition");
+        resources.load_dungeon_definition()?;
+
+        info!("Reading map generation parameters");
+        resources.load_generation_definition()?;
+
         let fireball = SpellDefinition { 
             name: Name("Fireball".to_string()), 
             effect: SpellEffectName::Fireball, 
@@ -92,6 +135,18 @@ impl ResourceManager {
         };
         resources.spell_definition.insert(SpellDefinitionId(1), scry);
 
+        let dig = SpellDefinition { 
+            name: Name("Dig".to_string()), 
+            effect: SpellEffectName::Dig, 
+            icon_states: ImageState {
+                current: "available".to_string(),
+                states: HashMap::from([("available".to_string(), ImageID(0))]),
+            }, 
+            casts: 2, 
+            reset_time_slot: TimeSlot::EndOfLevel, 
+        };
+        resources.spell_definition.insert(SpellDefinitionId(2), dig);
+
         Ok(resources)
     }
 
@@ -153,8 +208,9 @@ impl ResourceManager {
 
     //  ROOM TEMPLATES
 
+    // Sorted by id, so seeded generation picks the same template every time.
     pub fn get_all_room_templates(&self) -> impl Iterator<Item = (&RoomTemplateID, &RoomTemplate)> {
-        self.room_template_definitions.iter()
+        self.room_template_definitions.iter().sorted_by_key(|(id, _)| **id)
     }
 
     pub fn get_room_template(&self, room_template: RoomTemplateID) -> Option<&RoomTemplate> {
@@ -175,6 +231,134 @@ impl ResourceManager {
         Ok(())
     }
 
+    //  VAULTS
+
+    pub fn get_all_vaults(&self) -> impl Iterator<Item = (&VaultID, &Vault)> {
+        self.vault_definitions.iter().sorted_by_key(|(id, _)| **id)
+    }
+
+    pub fn get_vault(&self, vault: VaultID) -> Option<&Vault> {
+        self.vault_definitions.get(&vault)
+    }
+
+    pub fn load_vault_definitions(&mut self) -> Result<()> {
+        self.vault_definitions = serialize_vault::load_from_yaml(Path::new(VAULT_PATH))?;
+        Ok(())
+    }
+
+    pub fn save_vault_definitions(&self) -> Result<()> {
+        serialize_vault::save_to_yaml(&self.vault_definitions, Path::new(VAULT_PATH))?;
+        Ok(())
+    }
+
+    //  THEMES
+
+    pub fn get_theme(&self, theme: ThemeID) -> Option<&Theme> {
+        self.theme_definitions.get(&theme)
+    }
+
+    // One of the themes fitting the depth, picked at random.
+    pub fn pick_theme(&self, depth: u32) -> Option<ThemeID> {
+        let mut fitting: Vec<ThemeID> = self
+            .theme_definitions
+            .iter()
+            .filter(|(_, theme)| !theme.branch_only && theme.fits_depth(depth))
+            .map(|(id, _)| *id)
+            .collect();
+
+        if fitting.is_empty() {
+            return None;
+        }
+
+        fitting.sort();
+        Some(fitting[game_rng().gen_range(0..fitting.len())])
+    }
+
+    pub fn load_theme_definitions(&mut self) -> Result<()> {
+        self.theme_definitions = serialize_theme::load_from_yaml(Path::new(THEME_PATH))?;
+        Ok(())
+    }
+
+    pub fn save_theme_definitions(&self) -> Result<()> {
+        serialize_theme::save_to_yaml(&self.theme_definitions, Path::new(THEME_PATH))?;
+        Ok(())
+    }
+
+    //  SPECIAL LEVELS
+
+    // The final depth always needs the artifact and the exit, so it is never replaced.
+    pub fn get_special_level(&self, depth: u32) -> Option<&SpecialLevel> {
+        if self.is_final_depth(depth) {
+            return None;
+        }
+        self.special_levels.get(&depth)
+    }
+
+    pub fn load_special_levels(&mut self) -> Result<()> {
+        self.special_levels = serialize_special::load_from_yaml(Path::new(SPECIAL_LEVEL_PATH))?;
+        Ok(())
+    }
+
+    pub fn save_special_levels(&self) -> Result<()> {
+        serialize_special::save_to_yaml(&self.special_levels, Path::new(SPECIAL_LEVEL_PATH))?;
+        Ok(())
+    }
+
+    //  DUNGEON
+
+    pub fn get_final_depth(&self) -> u32 {
+        self.dungeon_definition.final_depth
+    }
+
+    pub fn is_final_depth(&self, depth: u32) -> bool {
+        depth >= self.dungeon_definition.final_depth
+    }
+
+    pub fn get_branch(&self, branch: BranchID) -> Option<&Branch> {
+        self.dungeon_definition
+            .branches
+            .iter()
+            .find(|definition| definition.id == branch)
+    }
+
+    // Branches that split off at this depth and return before the final depth.
+    pub fn get_branches_from(&self, depth: u32) -> impl Iterator<Item = &Branch> {
+        let final_depth = self.dungeon_definition.final_depth;
+        self.dungeon_definition
+            .branches
+            .iter()
+            .filter(move |branch| {
+                branch.from_depth == depth && branch.length > 0 && branch.last_depth() < final_depth
+            })
+    }
+
+    pub fn load_dungeon_definition(&mut self) -> Result<()> {
+        self.dungeon_definition = serialize_dungeon::load_from_yaml(Path::new(DUNGEON_PATH))?;
+        Ok(())
+    }
+
+    pub fn save_dungeon_definition(&self) -> Result<()> {
+        serialize_dungeon::save_to_yaml(&self.dungeon_definition, Path::new(DUNGEON_PATH))?;
+        Ok(())
+    }
+
+    //  MAP GENERATION
+
+    pub fn get_generation_parameters(&self, depth: u32) -> GenerationParameters {
+        self.generation_definition.parameters_for(depth)
+    }
+
+    pub fn load_generation_definition(&mut self) -> Result<()> {
+        self.generation_definition =
+            serialize_generation::load_from_yaml(Path::new(GENERATION_PATH))?;
+        Ok(())
+    }
+
+    pub fn save_generation_definition(&self) -> Result<()> {
+        serialize_generation::save_to_yaml(&self.generation_definition, Path::new(GENERATION_PATH))?;
+        Ok(())
+    }
+
     //  SPELLS
 
     pub fn get_spell(&self, spell: SpellDefinitionId) -> Option<&SpellDefinition> {
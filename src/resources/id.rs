@@ -19,4 +19,13 @@ pub struct ImageID(pub usize);
 pub struct SpellInstanceId(pub usize);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash, PartialOrd, Ord)]
-pub struct SpellDefinitionId(pub usize);
\ No newline at end of file
+pub struct SpellDefinitionId(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash, PartialOrd, Ord)]
+pub struct VaultID(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash, PartialOrd, Ord)]
+pub struct ThemeID(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash, PartialOrd, Ord)]
+pub struct BranchID(pub usize);
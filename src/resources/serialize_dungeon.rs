@@ -0,0 +1,69 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+
+use super::id::{BranchID, SpawnEntryID, ThemeID};
+
+// Settings for the dungeon as a whole, rather than for a single floor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DungeonDefinition {
+    // The floor holding the artifact. It has no stairs down, only the exit.
+    pub final_depth: u32,
+    #[serde(default)]
+    pub branches: Vec<Branch>,
+}
+
+impl Default for DungeonDefinition {
+    fn default() -> Self {
+        Self {
+            final_depth: 5,
+            branches: vec![],
+        }
+    }
+}
+
+// A side path of a few floors, reached through a second staircase on the floor it branches off from.
+// Its floors count their depth like the main line, and the stairs of its last floor lead back to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Branch {
+    pub id: BranchID,
+    pub name: String,
+    pub from_depth: u32,
+    // Chance for the floor at from_depth to have the second staircase.
+    pub chance: f64,
+    pub length: u32,
+    pub theme: Option<ThemeID>,
+    // Spawns on the branch's floors are picked and scaled as if they were this many floors deeper.
+    #[serde(default)]
+    pub depth_offset: u32,
+    // Spawned next to the stairs of the last floor.
+    pub reward: Option<SpawnEntryID>,
+}
+
+impl Branch {
+    pub fn last_depth(&self) -> u32 {
+        self.from_depth + self.length
+    }
+}
+
+pub fn save_to_yaml(dungeon: &DungeonDefinition, path: &Path) -> Result<()> {
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    serde_yaml::to_writer(writer, dungeon)?;
+
+    Ok(())
+}
+
+pub fn load_from_yaml(path: &Path) -> Result<DungeonDefinition> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let dungeon: DungeonDefinition = serde_yaml::from_reader(reader)?;
+
+    Ok(dungeon)
+}
@@ -7,6 +7,7 @@ use std::{
 
 use serde::{Deserialize, Serialize};
 
+use crate::component::effect::Effect;
 use crate::error::Result;
 use crate::map::tile::{GameTile, Los, Passable};
 
@@ -19,6 +20,26 @@ pub struct SerializedGameTile {
     pub image: ImageID,
     pub passable: Passable,
     pub los: Los,
+    #[serde(default = "default_movement_cost")]
+    pub movement_cost: u32,
+    #[serde(default)]
+    pub damage: u32,
+    #[serde(default)]
+    pub status_effect: Option<Effect>,
+    #[serde(default)]
+    pub flammable: bool,
+    #[serde(default)]
+    pub diggable: bool,
+    #[serde(default)]
+    pub noise: u32,
+    #[serde(default)]
+    pub health: Option<u32>,
+    #[serde(default)]
+    pub broken_into: Option<TileID>,
+}
+
+fn default_movement_cost() -> u32 {
+    1
 }
 
 impl SerializedGameTile {
@@ -29,6 +50,14 @@ impl SerializedGameTile {
             image: tile.image,
             passable: tile.passable,
             los: tile.los,
+            movement_cost: tile.movement_cost,
+            damage: tile.damage,
+            status_effect: tile.status_effect,
+            flammable: tile.flammable,
+            diggable: tile.diggable,
+            noise: tile.noise,
+            health: tile.health,
+            broken_into: tile.broken_into,
         }
     }
 
@@ -44,6 +73,14 @@ impl From<&SerializedGameTile> for GameTile {
             image: value.image,
             passable: value.passable,
             los: value.los,
+            movement_cost: value.movement_cost,
+            damage: value.damage,
+            status_effect: value.status_effect,
+            flammable: value.flammable,
+            diggable: value.diggable,
+            noise: value.noise,
+            health: value.health,
+            broken_into: value.broken_into,
         }
     }
 }
@@ -55,6 +92,14 @@ impl From<SerializedGameTile> for GameTile {
             image: value.image,
             passable: value.passable,
             los: value.los,
+            movement_cost: value.movement_cost,
+            damage: value.damage,
+            status_effect: value.status_effect,
+            flammable: value.flammable,
+            diggable: value.diggable,
+            noise: value.noise,
+            health: value.health,
+            broken_into: value.broken_into,
         }
     }
 }
@@ -0,0 +1,98 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::map::{
+    generation::{GenerationParameters, LevelStyle},
+    shape::RoomShapeWeights,
+};
+
+// Every field left out of an override keeps the value from shallower floors.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerationOverride {
+    pub depth: u32,
+    pub style: Option<LevelStyle>,
+    pub size_x: Option<u32>,
+    pub size_y: Option<u32>,
+    pub bsp_depth: Option<u32>,
+    pub min_room_side: Option<i32>,
+    pub min_room_area: Option<i32>,
+    pub neighbor_scan_distance: Option<i32>,
+    pub edge_threshold: Option<usize>,
+    pub max_edge_trim: Option<usize>,
+    pub cave_fill_chance: Option<f64>,
+    pub cave_smoothing_steps: Option<u32>,
+    pub vault_chance: Option<f64>,
+    pub room_shapes: Option<RoomShapeWeights>,
+    pub max_spawns_per_room: Option<usize>,
+    pub secret_door_chance: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenerationDefinition {
+    pub default: GenerationParameters,
+    pub overrides: Vec<GenerationOverride>,
+}
+
+impl GenerationDefinition {
+    // Overrides stack in order of depth, so deeper entries refine shallower ones.
+    pub fn parameters_for(&self, depth: u32) -> GenerationParameters {
+        let mut overrides: Vec<&GenerationOverride> = self
+            .overrides
+            .iter()
+            .filter(|entry| entry.depth <= depth)
+            .collect();
+        overrides.sort_by_key(|entry| entry.depth);
+
+        let mut parameters = self.default;
+        for entry in overrides {
+            parameters.style = entry.style.unwrap_or(parameters.style);
+            parameters.size_x = entry.size_x.unwrap_or(parameters.size_x);
+            parameters.size_y = entry.size_y.unwrap_or(parameters.size_y);
+            parameters.bsp_depth = entry.bsp_depth.unwrap_or(parameters.bsp_depth);
+            parameters.min_room_side = entry.min_room_side.unwrap_or(parameters.min_room_side);
+            parameters.min_room_area = entry.min_room_area.unwrap_or(parameters.min_room_area);
+            parameters.neighbor_scan_distance = entry
+                .neighbor_scan_distance
+                .unwrap_or(parameters.neighbor_scan_distance);
+            parameters.edge_threshold = entry.edge_threshold.unwrap_or(parameters.edge_threshold);
+            parameters.max_edge_trim = entry.max_edge_trim.unwrap_or(parameters.max_edge_trim);
+            parameters.cave_fill_chance =
+                entry.cave_fill_chance.unwrap_or(parameters.cave_fill_chance);
+            parameters.cave_smoothing_steps = entry
+                .cave_smoothing_steps
+                .unwrap_or(parameters.cave_smoothing_steps);
+            parameters.vault_chance = entry.vault_chance.unwrap_or(parameters.vault_chance);
+            parameters.room_shapes = entry.room_shapes.unwrap_or(parameters.room_shapes);
+            parameters.max_spawns_per_room = entry
+                .max_spawns_per_room
+                .unwrap_or(parameters.max_spawns_per_room);
+            parameters.secret_door_chance = entry
+                .secret_door_chance
+                .unwrap_or(parameters.secret_door_chance);
+        }
+
+        parameters
+    }
+}
+
+pub fn save_to_yaml(generation: &GenerationDefinition, path: &Path) -> Result<()> {
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    serde_yaml::to_writer(writer, generation)?;
+
+    Ok(())
+}
+
+pub fn load_from_yaml(path: &Path) -> Result<GenerationDefinition> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let generation: GenerationDefinition = serde_yaml::from_reader(reader)?;
+
+    Ok(generation)
+}
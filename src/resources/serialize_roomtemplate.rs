@@ -17,6 +17,8 @@ pub struct SerializedRoomTemplate {
     id: RoomTemplateID,
     requirements: Vec<RoomCriterion>,
     spawns: Vec<SpawnEntryID>,
+    #[serde(default)]
+    ambient_light: Option<u32>,
 }
 
 impl SerializedRoomTemplate {
@@ -25,6 +27,7 @@ impl SerializedRoomTemplate {
             id,
             requirements: template.requirements,
             spawns: template.spawns,
+            ambient_light: template.ambient_light,
         }
     }
 
@@ -38,6 +41,7 @@ impl From<&SerializedRoomTemplate> for RoomTemplate {
         Self {
             requirements: value.requirements.clone(),
             spawns: value.spawns.clone(),
+            ambient_light: value.ambient_light,
         }
     }
 }
@@ -47,6 +51,7 @@ impl From<SerializedRoomTemplate> for RoomTemplate {
         Self {
             requirements: value.requirements,
             spawns: value.spawns,
+            ambient_light: value.ambient_light,
         }
     }
 }
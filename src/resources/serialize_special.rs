@@ -0,0 +1,88 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::map::special::{EntryRule, ExitRule, SpecialLevel};
+use crate::spawning::vault::{Vault, VaultGlyph};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerializedSpecialLevel {
+    name: String,
+    depth: u32,
+    #[serde(default)]
+    entry: EntryRule,
+    #[serde(default)]
+    exit: ExitRule,
+    #[serde(default)]
+    arrival_message: Option<String>,
+    #[serde(default)]
+    legend: HashMap<char, VaultGlyph>,
+    layout: Vec<String>,
+}
+
+impl SerializedSpecialLevel {
+    pub fn new(level: SpecialLevel) -> Self {
+        Self {
+            name: level.name,
+            depth: level.depth,
+            entry: level.entry,
+            exit: level.exit,
+            arrival_message: level.arrival_message,
+            legend: level.layout.legend,
+            layout: level.layout.layout,
+        }
+    }
+
+    pub fn decompose(self) -> (u32, SpecialLevel) {
+        (self.depth, self.into())
+    }
+}
+
+impl From<SerializedSpecialLevel> for SpecialLevel {
+    fn from(value: SerializedSpecialLevel) -> Self {
+        Self {
+            name: value.name,
+            depth: value.depth,
+            layout: Vault {
+                requirements: vec![],
+                legend: value.legend,
+                layout: value.layout,
+            },
+            entry: value.entry,
+            exit: value.exit,
+            arrival_message: value.arrival_message,
+        }
+    }
+}
+
+pub fn save_to_yaml(levels: &HashMap<u32, SpecialLevel>, path: &Path) -> Result<()> {
+    let mut levels: Vec<SerializedSpecialLevel> = levels
+        .values()
+        .map(|level| SerializedSpecialLevel::new(level.clone()))
+        .collect();
+
+    levels.sort_by_key(|entry| entry.depth);
+
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    serde_yaml::to_writer(writer, &levels)?;
+
+    Ok(())
+}
+
+pub fn load_from_yaml(path: &Path) -> Result<HashMap<u32, SpecialLevel>> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+
+    let levels: Vec<SerializedSpecialLevel> = serde_yaml::from_reader(reader)?;
+    let levels = levels.into_iter().map(|entry| entry.decompose());
+    let levels = HashMap::from_iter(levels);
+
+    Ok(levels)
+}
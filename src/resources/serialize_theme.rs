@@ -0,0 +1,97 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::map::theme::{Decoration, Theme, TileVariant};
+
+use super::id::{RoomTemplateID, SpawnEntryID, ThemeID};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerializedTheme {
+    id: ThemeID,
+    name: String,
+    min_depth: u32,
+    #[serde(default)]
+    max_depth: Option<u32>,
+    #[serde(default)]
+    floor_tiles: Vec<TileVariant>,
+    #[serde(default)]
+    wall_tiles: Vec<TileVariant>,
+    #[serde(default)]
+    decorations: Vec<Decoration>,
+    #[serde(default)]
+    ambient_spawns: Vec<SpawnEntryID>,
+    #[serde(default)]
+    room_templates: Vec<RoomTemplateID>,
+    #[serde(default)]
+    branch_only: bool,
+}
+
+impl SerializedTheme {
+    pub fn new(id: ThemeID, theme: Theme) -> Self {
+        Self {
+            id,
+            name: theme.name,
+            min_depth: theme.min_depth,
+            max_depth: theme.max_depth,
+            floor_tiles: theme.floor_tiles,
+            wall_tiles: theme.wall_tiles,
+            decorations: theme.decorations,
+            ambient_spawns: theme.ambient_spawns,
+            room_templates: theme.room_templates,
+            branch_only: theme.branch_only,
+        }
+    }
+
+    pub fn decompose(self) -> (ThemeID, Theme) {
+        (self.id, self.into())
+    }
+}
+
+impl From<SerializedTheme> for Theme {
+    fn from(value: SerializedTheme) -> Self {
+        Self {
+            name: value.name,
+            min_depth: value.min_depth,
+            max_depth: value.max_depth,
+            floor_tiles: value.floor_tiles,
+            wall_tiles: value.wall_tiles,
+            decorations: value.decorations,
+            ambient_spawns: value.ambient_spawns,
+            room_templates: value.room_templates,
+            branch_only: value.branch_only,
+        }
+    }
+}
+
+pub fn save_to_yaml(themes: &HashMap<ThemeID, Theme>, path: &Path) -> Result<()> {
+    let mut themes: Vec<SerializedTheme> = themes
+        .iter()
+        .map(|(id, theme)| SerializedTheme::new(*id, theme.clone()))
+        .collect();
+
+    themes.sort_by_key(|entry| entry.id);
+
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    serde_yaml::to_writer(writer, &themes)?;
+
+    Ok(())
+}
+
+pub fn load_from_yaml(path: &Path) -> Result<HashMap<ThemeID, Theme>> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+
+    let themes: Vec<SerializedTheme> = serde_yaml::from_reader(reader)?;
+    let themes = themes.into_iter().map(|entry| entry.decompose());
+    let themes = HashMap::from_iter(themes);
+
+    Ok(themes)
+}
@@ -0,0 +1,76 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::spawning::{
+    roomtemplate::RoomCriterion,
+    vault::{Vault, VaultGlyph},
+};
+
+use super::VaultID;
+use crate::error::Result;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerializedVault {
+    id: VaultID,
+    requirements: Vec<RoomCriterion>,
+    #[serde(default)]
+    legend: HashMap<char, VaultGlyph>,
+    layout: Vec<String>,
+}
+
+impl SerializedVault {
+    pub fn new(id: VaultID, vault: Vault) -> Self {
+        Self {
+            id,
+            requirements: vault.requirements,
+            legend: vault.legend,
+            layout: vault.layout,
+        }
+    }
+
+    pub fn decompose(self) -> (VaultID, Vault) {
+        (self.id, self.into())
+    }
+}
+
+impl From<SerializedVault> for Vault {
+    fn from(value: SerializedVault) -> Self {
+        Self {
+            requirements: value.requirements,
+            legend: value.legend,
+            layout: value.layout,
+        }
+    }
+}
+
+pub fn save_to_yaml(vaults: &HashMap<VaultID, Vault>, path: &Path) -> Result<()> {
+    let mut vaults: Vec<SerializedVault> = vaults
+        .iter()
+        .map(|(id, vault)| SerializedVault::new(*id, vault.clone()))
+        .collect();
+
+    vaults.sort_by_key(|entry| entry.id);
+
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    serde_yaml::to_writer(writer, &vaults)?;
+
+    Ok(())
+}
+
+pub fn load_from_yaml(path: &Path) -> Result<HashMap<VaultID, Vault>> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+
+    let vaults: Vec<SerializedVault> = serde_yaml::from_reader(reader)?;
+    let vaults = vaults.into_iter().map(|entry| entry.decompose());
+    let vaults = HashMap::from_iter(vaults);
+
+    Ok(vaults)
+}
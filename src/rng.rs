@@ -0,0 +1,42 @@
+use std::cell::RefCell;
+
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+/* RANDOMNESS
+    Every random roll goes through this generator instead of rand's thread_rng,
+    so tooling can seed it and generate the same levels again.
+ */
+
+thread_local! {
+    static GENERATOR: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+pub fn set_seed(seed: u64) {
+    GENERATOR.with(|generator| *generator.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+// Handle to the generator of the current thread, used like the one rand::thread_rng returns.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GameRng;
+
+pub fn game_rng() -> GameRng {
+    GameRng
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        GENERATOR.with(|generator| generator.borrow_mut().next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        GENERATOR.with(|generator| generator.borrow_mut().next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        GENERATOR.with(|generator| generator.borrow_mut().fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        GENERATOR.with(|generator| generator.borrow_mut().try_fill_bytes(dest))
+    }
+}
@@ -1,25 +1,35 @@
 use std::collections::{HashSet, VecDeque};
 use log::{debug, warn};
 use petgraph::{graph::NodeIndex, visit::IntoNodeReferences, Graph};
-use rand::{thread_rng, Rng};
-use roomtemplate::RoomTemplate;
+use rand::{seq::SliceRandom, Rng};
 use spawnentry::{SpawnEntry, SpawnEntryType};
+use vault::VaultGlyph;
 
-use crate::error::Result;
+use crate::rng::game_rng;
+use crate::component::{image::ImageState, tags::StairsDown, Destination, Name};
+use crate::error::{Error, Result};
 use crate::{
     map::{
         generation::RoomGraph,
         room::{QuestGenerationData, Room, RoomGenerationData},
-        utils::Coordinate,
+        theme::Theme,
+        tile::Passable,
+        utils::{Coordinate, DOWN, LEFT, RIGHT, UP},
         GameMap,
     },
-    resources::{id::RoomTemplateID, ResourceManager, DOOR_SPAWNABLE},
+    resources::{
+        id::{ImageID, RoomTemplateID, SpawnEntryID, VaultID},
+        ResourceManager, DOOR_SPAWNABLE, MAIN_BRANCH, SECRET_DOOR_SPAWNABLE,
+    },
     world::World,
 };
 
 pub mod entitytemplate;
 pub mod roomtemplate;
 pub mod spawnentry;
+pub mod vault;
+
+const HIDDEN_IMAGE_STATE: &'static str = "hidden";
 
 pub fn spawn_all_entities(
     map: &GameMap,
@@ -32,13 +42,41 @@ pub fn spawn_all_entities(
         spawn_doors(room, map, world, resources)?;
 
         // if spawn player, don't also spawn template
+        // on the final floor the way out is next to where the player arrives
         if let Some(SpawnEntryType::Player(_)) = room.extra_spawn {
-            spawn_player(room, map, world, resources)?;
+            spawn_extra(SpawnEntryType::Player(0), room, map, world, resources)?;
+            if world.get_player_id().is_err() {
+                return Err("Found no free spot for the player in the start room".into());
+            }
+            if resources.is_final_depth(map.depth) {
+                spawn_extra(SpawnEntryType::Exit(0), room, map, world, resources)?;
+            }
+            continue;
+        }
+
+        // vaults bring their own entities instead of a template
+        if let Some(vault) = room.vault {
+            spawn_vault(room, vault, map, world, resources)?;
+        }
+
+        // the artifact room brings its own guardian instead of a template
+        if let Some(SpawnEntryType::Artifact(_)) = room.extra_spawn {
+            spawn_extra(SpawnEntryType::Artifact(0), room, map, world, resources)?;
             continue;
         }
 
         if let Some(SpawnEntryType::Stairs(_)) = room.extra_spawn {
-            spawn_stairs(room, map, world, resources)?;
+            spawn_extra(SpawnEntryType::Stairs(0), room, map, world, resources)?;
+
+            // the reward of a branch waits next to the stairs leading out of it, instead of a template
+            if let Some(reward) = branch_reward(map, resources) {
+                spawn_room(room, &[reward], map, world, resources)?;
+                continue;
+            }
+        }
+
+        if room.vault.is_some() {
+            continue;
         }
 
         // spawn from templates
@@ -49,24 +87,103 @@ pub fn spawn_all_entities(
             continue;
         };
 
-        spawn_room(room, room_template, map, world, resources)?;
+        spawn_room(room, &room_template.spawns, map, world, resources)?;
+    }
+
+    // the spawn tables gave no room to the player
+    if world.get_player_id().is_err() {
+        return Err(Error::NoPlayerFound);
     }
 
+    if let Some(theme) = map.theme.and_then(|theme| resources.get_theme(theme)) {
+        for room in map.room_graph.node_weights() {
+            spawn_theme(room, theme, map, world, resources)?;
+        }
+    }
+
+    set_stair_destinations(map.next_destination(resources), world)?;
+    spawn_branch_stairs(map, world, resources)?;
+
     world.debug_print_all();
     Ok(())
 }
 
-fn spawn_player(
+/* BRANCHES
+    Floors a branch splits off from may get a second staircase, placed into a room of its own.
+    Every staircase is given the destination it leads to, which is where descending takes the player.
+ */
+
+fn spawn_branch_stairs(map: &GameMap, world: &mut World, resources: &ResourceManager) -> Result<()> {
+    if map.branch != MAIN_BRANCH {
+        return Ok(());
+    }
+
+    let mut candidates: Vec<&Room> = map
+        .room_graph
+        .node_weights()
+        .filter(|room| room.extra_spawn.is_none() && room.vault.is_none() && room.room_depth.is_some())
+        .collect();
+    candidates.shuffle(&mut game_rng());
+
+    let mut candidates = candidates.into_iter();
+    for branch in resources.get_branches_from(map.depth) {
+        if !game_rng().gen_bool(branch.chance) {
+            continue;
+        }
+        let Some(room) = candidates.next() else {
+            break;
+        };
+
+        spawn_extra(SpawnEntryType::Stairs(0), room, map, world, resources)?;
+
+        let destination = Destination {
+            branch: branch.id,
+            depth: map.depth + 1,
+        };
+        for stairs in set_stair_destinations(destination, world)? {
+            world.add_component(stairs, Name(format!("Stairs to the {}", branch.name)))?;
+        }
+    }
+    Ok(())
+}
+
+// Only the last floor of a branch holds its reward.
+fn branch_reward(map: &GameMap, resources: &ResourceManager) -> Option<SpawnEntryID> {
+    let branch = resources.get_branch(map.branch)?;
+    (map.depth == branch.last_depth()).then_some(branch.reward).flatten()
+}
+
+// Gives every staircase without a destination this one, and returns them.
+fn set_stair_destinations(destination: Destination, world: &mut World) -> Result<Vec<usize>> {
+    let Some(stairs) = world.borrow_component_vec::<StairsDown>() else {
+        return Ok(vec![]);
+    };
+
+    let unassigned: Vec<usize> = stairs
+        .iter()
+        .enumerate()
+        .filter(|(_, tag)| tag.is_some())
+        .map(|(entity, _)| entity)
+        .filter(|entity| world.borrow_entity_component::<Destination>(*entity).is_none())
+        .collect();
+
+    for entity in &unassigned {
+        world.add_component(*entity, destination)?;
+    }
+    Ok(unassigned)
+}
+
+fn spawn_extra(
+    spawn_entry: SpawnEntryType,
     room: &Room,
     map: &GameMap,
     world: &mut World,
     resources: &ResourceManager,
 ) -> Result<()> {
-    let spawn_entry = SpawnEntryType::Player(0);
     let mut quest_data = QuestGenerationData {};
     let room_data = RoomGenerationData {
         room: room.into(),
-        level_depth: map.depth,
+        level_depth: map.spawn_depth(resources),
         room_depth: 0,
     };
 
@@ -74,28 +191,123 @@ fn spawn_player(
     Ok(())
 }
 
+fn spawn_vault(
+    room: &Room,
+    vault: VaultID,
+    map: &GameMap,
+    world: &mut World,
+    resources: &ResourceManager,
+) -> Result<()> {
+    let Some(vault) = resources.get_vault(vault) else {
+        return Err("Could not find vault!".into());
+    };
+
+    for (offset, glyph) in vault.glyphs() {
+        let VaultGlyph::Spawnable(spawnable) = glyph else {
+            continue;
+        };
+
+        let templates = resources.get_entity_templates(spawnable);
+        let position = room.extends.top_left + offset;
+        world.spawn_from_templates(&templates, map.spawn_depth(resources), position, resources)?;
+    }
+    Ok(())
+}
+
 fn spawn_doors(
     room: &Room,
     map: &GameMap,
     world: &mut World,
     resources: &ResourceManager,
 ) -> Result<()> {
-    let id = DOOR_SPAWNABLE;
-    let door_templates = resources.get_entity_templates(id);
+    let secret_door_chance = resources.get_generation_parameters(map.depth).secret_door_chance;
 
     for position in &room.door_locations {
+        // neighboring rooms share the wall between them, and with it the door
+        if !world.get_entities_at_coordinate(*position).is_empty() {
+            continue;
+        }
+
+        let id = match game_rng().gen_bool(secret_door_chance) {
+            true => SECRET_DOOR_SPAWNABLE,
+            false => DOOR_SPAWNABLE,
+        };
+        let door_templates = resources.get_entity_templates(id);
+
         debug!("Attempting to spawn door at {position:?}");
         match world.spawn_from_templates(&door_templates, map.depth, *position, resources) {
             Err(error) => warn!("{error}"),
+            Ok(door) if id == SECRET_DOOR_SPAWNABLE => blend_into_wall(door, *position, map, world, resources),
             Ok(_) => {}
         }
     }
     Ok(())
 }
 
+// Hidden secret doors take on the look of the wall around them, themes paint walls in more than one way.
+fn blend_into_wall(door: usize, position: Coordinate, map: &GameMap, world: &mut World, resources: &ResourceManager) {
+    let wall_image = [UP, DOWN, LEFT, RIGHT]
+        .into_iter()
+        .filter_map(|direction| map.get_tile_at(position + direction, resources))
+        .find(|tile| tile.passable == Passable::None)
+        .map(|tile| tile.image);
+    let Some(wall_image) = wall_image else {
+        return;
+    };
+
+    if let Some(image_states) = world.borrow_entity_component_mut::<ImageState>(door) {
+        image_states.states.insert(HIDDEN_IMAGE_STATE.to_string(), wall_image);
+    }
+    if let Some(image) = world.borrow_entity_component_mut::<ImageID>(door) {
+        *image = wall_image;
+    }
+}
+
+// Ambient spawns go everywhere but the player's room, decorations only into rooms without a vault.
+fn spawn_theme(
+    room: &Room,
+    theme: &Theme,
+    map: &GameMap,
+    world: &mut World,
+    resources: &ResourceManager,
+) -> Result<()> {
+    if let Some(SpawnEntryType::Player(_)) = room.extra_spawn {
+        return Ok(());
+    }
+    if room.room_depth.is_none() {
+        return Ok(());
+    }
+
+    spawn_room(room, &theme.ambient_spawns, map, world, resources)?;
+
+    if room.vault.is_some() {
+        return Ok(());
+    }
+
+    for coordinate in map.get_room_floor(&room.extends, resources) {
+        let next_to_door = room
+            .door_locations
+            .iter()
+            .any(|door| door.distance(coordinate) < 2.0);
+        if next_to_door || !world.get_entities_at_coordinate(coordinate).is_empty() {
+            continue;
+        }
+
+        let decoration = theme
+            .decorations
+            .iter()
+            .find(|decoration| game_rng().gen_bool(decoration.chance));
+        if let Some(decoration) = decoration {
+            let templates = resources.get_entity_templates(decoration.spawnable);
+            world.spawn_from_templates(&templates, map.spawn_depth(resources), coordinate, resources)?;
+        }
+    }
+    Ok(())
+}
+
 fn spawn_room(
     room: &Room, 
-    room_template: &RoomTemplate,
+    spawns: &[SpawnEntryID],
     map: &GameMap,
     world: &mut World,
     resources: &ResourceManager,
@@ -103,7 +315,7 @@ fn spawn_room(
     let room_depth = room.room_depth.unwrap();
     let mut quest_data = QuestGenerationData {};
 
-    for spawn_entry in &room_template.spawns {
+    for spawn_entry in spawns {
         let Some(spawn_entry) = resources.get_spawn_entry(*spawn_entry) else {
             continue;
         };
@@ -111,7 +323,7 @@ fn spawn_room(
         let container = world.get_room_data_from(room);
         let room_data = RoomGenerationData {
             room: container,
-            level_depth: map.depth,
+            level_depth: map.spawn_depth(resources),
             room_depth,
         };
 
@@ -123,34 +335,20 @@ fn spawn_room(
     Ok(())
 }
 
-fn spawn_stairs(
-    room: &Room,
-    map: &GameMap,
-    world: &mut World,
-    resources: &ResourceManager,
-) -> Result<()> {
-    let spawn_entry = SpawnEntryType::Stairs(0);
-    let mut quest_data = QuestGenerationData {};
-    let room_data = RoomGenerationData {
-        room: room.into(),
-        level_depth: map.depth,
-        room_depth: 0,
-    };
-
-    spawn_entry.spawn(&room_data, &mut quest_data, world, map, resources)?;
-    Ok(())
+// The player starts in the top left most room that isn't a vault.
+pub fn find_start_room(room_graph: &RoomGraph) -> Option<NodeIndex> {
+    room_graph
+        .node_references()
+        .filter(|(_, room)| room.vault.is_none())
+        .min_by_key(|(_, room)| room.extends.top_left)
+        .map(|(index, _)| index)
 }
 
 pub fn flood_fill_spawn_tables(map: &GameMap, resources: &ResourceManager) -> GameMap {
     let mut new_graph: RoomGraph = Graph::default();
     new_graph.clone_from(&map.room_graph);
 
-    let mut top_left_corners: Vec<(NodeIndex, Coordinate)> = new_graph
-        .node_references()
-        .map(|(index, room)| (index, room.extends.top_left))
-        .collect();
-    top_left_corners.sort_unstable_by_key(|(_, coord)| *coord);
-    let (start_index, _) = top_left_corners[0];
+    let start_index = find_start_room(&new_graph).expect("Level has no room to start in");
     let mut visited: HashSet<NodeIndex> = HashSet::new();
     let mut fill_queue: VecDeque<NodeIndex> = VecDeque::new();
 
@@ -179,16 +377,18 @@ pub fn flood_fill_spawn_tables(map: &GameMap, resources: &ResourceManager) -> Ga
         let room_depth = new_graph[index].room_depth.unwrap();
         let room_data = RoomGenerationData {
             room: room.into(),
-            level_depth: map.depth,
+            level_depth: map.spawn_depth(resources),
             room_depth,
         };
 
         let quest_data = QuestGenerationData {};
 
         let mut template = None;
+        let theme = map.theme.and_then(|theme| resources.get_theme(theme));
         let viable_templates: Vec<RoomTemplateID> = resources
             .get_all_room_templates()
             .into_iter()
+            .filter(|(id, _)| theme.map_or(true, |theme| theme.allows_template(**id)))
             .filter_map(
                 |(id, template)| match template.validate(&room_data, &quest_data) {
                     true => Some(*id),
@@ -198,7 +398,7 @@ pub fn flood_fill_spawn_tables(map: &GameMap, resources: &ResourceManager) -> Ga
             .collect();
 
         if viable_templates.len() > 0 {
-            let random_index = thread_rng().gen_range(0..viable_templates.len());
+            let random_index = game_rng().gen_range(0..viable_templates.len());
             template = Some(viable_templates[random_index]);
         };
 
@@ -209,7 +409,10 @@ pub fn flood_fill_spawn_tables(map: &GameMap, resources: &ResourceManager) -> Ga
         }
 
         if fill_queue.is_empty() {
-            extra_spawn = Some(SpawnEntryType::Stairs(0));
+            extra_spawn = match resources.is_final_depth(map.depth) {
+                true => Some(SpawnEntryType::Artifact(0)),
+                false => Some(SpawnEntryType::Stairs(0)),
+            };
         }
 
         new_graph[index] = Room {
@@ -1,15 +1,17 @@
 use std::{collections::HashMap, fmt::Debug};
 
 use derive_entity_template::EntityTemplateEnum;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
+use crate::rng::game_rng;
 use crate::component::combat;
 use crate::component::responses::{DeathResponse, NoiseResponse, PickupResponse, SpellResponse};
 use crate::component::health::{self};
 use crate::component::items::{self, Coins};
 use crate::component::spell::Spellbook;
-use crate::component::tags::StairsDown;
+use crate::component::stats::RunStats;
+use crate::component::tags::{Artifact, Exit, Secret, StairsDown};
 use crate::error::Result;
 use crate::resources::id::SpellDefinitionId;
 use crate::resources::{self, ResourceManager};
@@ -22,7 +24,7 @@ use crate::{
         health::Health,
         image::ImageState,
         tags::{Door, Monster},
-        Collision, Name, SightBlocking,
+        Collision, LightSource, Movement, Name, SightBlocking,
     },
     event::ResponseFuctionName,
     map::tile::{Los, Passable},
@@ -39,14 +41,18 @@ pub enum EntityTemplateEnum {
     Core(CoreTemplate),
     Combat(CombatTemplate),
     Door(DoorTemplate),
+    Secret(SecretTemplate),
     Monster(MonsterTemplate),
     Player(PlayerTemplate),
     Stairs(StairsTemplate),
+    Exit(ExitTemplate),
+    Artifact(ArtifactTemplate),
     Interactable(InteractableTemplate),
     Pickup(PickupTemplate),
     Inventory(InventoryTemplate),
     Destructible(DestructibleTemplate),
     Spellbook(SpellbookTemplate),
+    Light(LightTemplate),
 }
 
 // Template Definitions
@@ -66,6 +72,7 @@ pub struct CoreTemplate {
     pub image: ImageID,
     pub collision: Passable,
     pub los: Los,
+    pub movement: Option<Movement>,
 }
 
 impl EntityTemplate for CoreTemplate {
@@ -80,6 +87,10 @@ impl EntityTemplate for CoreTemplate {
         world.add_component(entity, collision)?;
         world.add_component(entity, sight_block)?;
 
+        if let Some(movement) = self.movement {
+            world.add_component(entity, movement)?;
+        }
+
         Ok(())
     }
 }
@@ -176,6 +187,48 @@ impl EntityTemplate for StairsTemplate {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExitTemplate;
+
+impl EntityTemplate for ExitTemplate {
+    fn add_components(&self, entity: usize, world: &mut World, _depth: u32, _resources: &ResourceManager) -> Result<()> {
+        world.add_component(entity, Exit)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightTemplate {
+    pub radius: u32,
+}
+
+impl EntityTemplate for LightTemplate {
+    fn add_components(&self, entity: usize, world: &mut World, _depth: u32, _resources: &ResourceManager) -> Result<()> {
+        world.add_component(entity, LightSource { radius: self.radius })?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretTemplate;
+
+impl EntityTemplate for SecretTemplate {
+    fn add_components(&self, entity: usize, world: &mut World, _depth: u32, _resources: &ResourceManager) -> Result<()> {
+        world.add_component(entity, Secret)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactTemplate;
+
+impl EntityTemplate for ArtifactTemplate {
+    fn add_components(&self, entity: usize, world: &mut World, _depth: u32, _resources: &ResourceManager) -> Result<()> {
+        world.add_component(entity, Artifact)?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerTemplate {
     level: u32,
@@ -187,7 +240,7 @@ impl EntityTemplate for PlayerTemplate {
         let xp = Xp::new(self.level);
 
         let mut attributes = self.attributes.clone();
-        let random_stat_index = thread_rng().gen_range(0..=2);
+        let random_stat_index = game_rng().gen_range(0..=2);
         match random_stat_index {
             0 => attributes.might += 1,
             1 => attributes.wit += 1,
@@ -197,6 +250,7 @@ impl EntityTemplate for PlayerTemplate {
 
         world.add_component(entity, attributes)?;
         world.add_component(entity, xp)?;
+        world.add_component(entity, RunStats::default())?;
         world.mark_as_player(entity)?;
 
         Ok(())
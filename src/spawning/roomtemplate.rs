@@ -9,6 +9,8 @@ use crate::{
 pub struct RoomTemplate {
     pub requirements: Vec<RoomCriterion>,
     pub spawns: Vec<SpawnEntryID>,
+    // Light level of the whole room, the default ambient light when unset. See map::lighting.
+    pub ambient_light: Option<u32>,
 }
 
 impl RoomTemplate {
@@ -1,8 +1,8 @@
-use itertools::Itertools;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashSet, fmt::Debug};
 
+use crate::rng::game_rng;
 use crate::error::Result;
 use crate::{
     component::{tags::Door, Position},
@@ -13,7 +13,8 @@ use crate::{
     },
     resources::{
         id::{SpawnEntryID, SpawnableID},
-        ResourceManager, PLAYER_SPAWNENTRY, STAIRS_SPAWNENTRY,
+        ResourceManager, ARTIFACT_SPAWNENTRY, EXIT_SPAWNENTRY, PLAYER_SPAWNENTRY,
+        STAIRS_SPAWNENTRY,
     },
     world::World,
 };
@@ -52,6 +53,8 @@ pub enum SpawnEntryType {
     Union(SpawnUnion),
     Player(u32),
     Stairs(u32),
+    Exit(u32),
+    Artifact(u32),
 }
 
 impl SpawnEntry for SpawnEntryType {
@@ -62,6 +65,8 @@ impl SpawnEntry for SpawnEntryType {
             Self::Union(spawn) => spawn.evaluate(room_data, quest_data),
             Self::Player(_) => true,
             Self::Stairs(_) => true,
+            Self::Exit(_) => true,
+            Self::Artifact(_) => true,
         }
     }
 
@@ -89,6 +94,18 @@ impl SpawnEntry for SpawnEntryType {
                 };
                 spawn.spawn(room_data, quest_data, world, map, resources)
             }
+            Self::Exit(_) => {
+                let Some(spawn) = resources.get_spawn_entry(EXIT_SPAWNENTRY) else {
+                    return Err("Could not find spawn table for exit!".into());
+                };
+                spawn.spawn(room_data, quest_data, world, map, resources)
+            }
+            Self::Artifact(_) => {
+                let Some(spawn) = resources.get_spawn_entry(ARTIFACT_SPAWNENTRY) else {
+                    return Err("Could not find spawn table for artifact!".into());
+                };
+                spawn.spawn(room_data, quest_data, world, map, resources)
+            }
         }
     }
 }
@@ -117,7 +134,7 @@ impl SpawnEntry for SpawnSelection {
         map: &GameMap,
         resources: &ResourceManager,
     ) -> Result<()> {
-        let random_index = thread_rng().gen_range(0.. self.sub_entries.len());
+        let random_index = game_rng().gen_range(0.. self.sub_entries.len());
         let entry_id = self.sub_entries[random_index];
 
         let Some(entry) = resources.get_spawn_entry(entry_id) else {
@@ -209,15 +226,11 @@ impl ConcreteSpawn {
         map: &GameMap,
         resources: &ResourceManager,
     ) -> Result<Coordinate> {
-        let x_range = room.extends.top_left.x + 1..room.extends.bottom_right.x;
-        let y_range = room.extends.top_left.y + 1..room.extends.bottom_right.y;
-
-        let inside_locations = x_range
-            .cartesian_product(y_range)
-            .map(|(x, y)| Coordinate { x, y });
+        let inside_locations = map.get_room_floor(&room.extends, resources);
 
         // never allow spawning on top of other entities
         let mut filtered_locations: Vec<Coordinate> = inside_locations
+            .into_iter()
             .filter(|location| world.get_entities_at_coordinate(*location).is_empty())
             .collect();
 
@@ -238,12 +251,12 @@ impl ConcreteSpawn {
             return Err("No legal location found".into());
         };
 
-        let random_index = thread_rng().gen_range(0..filtered_locations.len());
+        let random_index = game_rng().gen_range(0..filtered_locations.len());
         Ok(filtered_locations[random_index])
     }
 
     fn get_random_amount(&self) -> u32 {
-        thread_rng().gen_range(self.min_amount..=self.max_amount)
+        game_rng().gen_range(self.min_amount..=self.max_amount)
     }
 }
 
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    map::{
+        boxextends::BoxExtends,
+        room::{QuestGenerationData, RoomGenerationData},
+        utils::{Axis, Coordinate},
+    },
+    resources::{
+        id::{SpawnableID, TileID},
+        FLOOR_TILEID, WALL_TILEID,
+    },
+};
+
+use super::roomtemplate::RoomCriterion;
+
+// Vaults are hand-drawn rooms. Each character of the layout is looked up in the legend
+// and becomes either a tile or a spawnable standing on floor.
+// '#' and '.' mean wall and floor unless the legend says otherwise, blanks are left untouched.
+// Layouts should include their outer walls, doors are added where corridors meet them.
+//
+// Requirements are checked when the vault is placed, before rooms are connected,
+// so criteria about the distance from the start always see a room depth of 0.
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum VaultGlyph {
+    Tile(TileID),
+    Spawnable(SpawnableID),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Vault {
+    pub requirements: Vec<RoomCriterion>,
+    pub legend: HashMap<char, VaultGlyph>,
+    pub layout: Vec<String>,
+}
+
+impl Vault {
+    pub fn validate(
+        &self,
+        room_data: &RoomGenerationData,
+        quest_data: &QuestGenerationData,
+    ) -> bool {
+        self.requirements
+            .iter()
+            .all(|criterion| criterion.validate(room_data, quest_data))
+    }
+
+    pub fn width(&self) -> i32 {
+        self.layout
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0) as i32
+    }
+
+    pub fn height(&self) -> i32 {
+        self.layout.len() as i32
+    }
+
+    pub fn fits_in(&self, area: &BoxExtends) -> bool {
+        self.width() <= area.get_axis_size(Axis::Horizontal)
+            && self.height() <= area.get_axis_size(Axis::Vertical)
+    }
+
+    // Every glyph of the layout with its offset from the top left corner.
+    pub fn glyphs(&self) -> impl Iterator<Item = (Coordinate, VaultGlyph)> + '_ {
+        self.layout.iter().enumerate().flat_map(move |(y, row)| {
+            row.chars().enumerate().filter_map(move |(x, character)| {
+                let offset = Coordinate {
+                    x: x as i32,
+                    y: y as i32,
+                };
+                self.get_glyph(character).map(|glyph| (offset, glyph))
+            })
+        })
+    }
+
+    fn get_glyph(&self, character: char) -> Option<VaultGlyph> {
+        if let Some(glyph) = self.legend.get(&character) {
+            return Some(*glyph);
+        }
+
+        match character {
+            '#' => Some(VaultGlyph::Tile(WALL_TILEID)),
+            '.' => Some(VaultGlyph::Tile(FLOOR_TILEID)),
+            _ => None,
+        }
+    }
+}
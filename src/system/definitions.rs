@@ -1,13 +1,27 @@
 use log::{debug, warn};
+use rand::Rng;
 
-use crate::component::attributes::{Xp, XpStatus};
+use crate::rng::game_rng;
+use crate::component::attributes::{self, Attributes, Xp, XpStatus};
+use crate::component::effect::{Effect, EffectHandler};
 use crate::component::spell::{self, Spellbook};
+use crate::component::combat::DamageType;
+use crate::component::stats::{self, LastAttacker, RunStats};
+use crate::component::tags::{Monster, Secret};
+use crate::component::{Name, Position};
 use crate::error::{Error, Result};
 use crate::event::combat_events::DeathEvent;
-use crate::event::spell_events;
+use crate::event::{response_functions, spell_events, stealth_events};
 use crate::{
-    component::{behavior::Behavior, health::Health},
-    map::GameMap,
+    component::{behavior::{Behavior, BehaviorState}, health::Health},
+    logger,
+    map::{
+        fov, lighting,
+        los::line_of_sight,
+        special::{self, ExitRule},
+        utils::Coordinate,
+        GameMap,
+    },
     resources::ResourceManager,
     world::World,
 };
@@ -34,12 +48,191 @@ pub fn reap_units(world: &mut World, map: &mut GameMap, resources: &ResourceMana
     let event = DeathEvent { source: 0 };
     for entity in reaped_entities {
         world.send_event(map, resources, &event, entity)?;
+        record_kill(entity, world);
         world.remove_entity(entity)?;
     }
 
     Ok(())
 }
 
+fn record_kill(entity: usize, world: &mut World) {
+    if world.borrow_entity_component::<Monster>(entity).is_none() {
+        return;
+    }
+
+    let Some(Name(name)) = world.borrow_entity_component::<Name>(entity).cloned() else {
+        return;
+    };
+
+    let Ok(player) = world.get_player_id() else {
+        return;
+    };
+
+    // Monsters felled by terrain, effects or each other don't count as the player's kills.
+    if world.borrow_entity_component::<LastAttacker>(entity) != Some(&LastAttacker(Some(player))) {
+        return;
+    }
+
+    if let Some(stats) = world.borrow_entity_component_mut::<RunStats>(player) {
+        stats.register_kill(&name);
+    }
+}
+
+pub fn track_floor_progress(
+    world: &mut World,
+    map: &mut GameMap,
+    _resources: &ResourceManager,
+) -> Result<()> {
+    let Ok(player) = world.get_player_id() else {
+        Err(Error::NoPlayerFound)?
+    };
+
+    let Some(stats) = world.borrow_entity_component_mut::<RunStats>(player) else {
+        return Err("Player has no run stats".into());
+    };
+
+    stats.turns += 1;
+
+    let floor = stats.floor_mut(map.depth);
+    floor.turns += 1;
+    floor.tiles_explored = map.explored.borrow().len() as u32;
+    Ok(())
+}
+
+// Opens the way down of a special level once its exit rule is met.
+pub fn special_level_exits(
+    world: &mut World,
+    map: &mut GameMap,
+    resources: &ResourceManager,
+) -> Result<()> {
+    let Some(level) = resources.get_special_level(map.depth) else {
+        return Ok(());
+    };
+    let ExitRule::DefeatBoss(_) = level.exit else {
+        return Ok(());
+    };
+
+    if special::is_boss_alive(world) || special::has_open_exit(world) {
+        return Ok(());
+    }
+
+    special::open_exits(level, map, world, resources)?;
+    logger::log_message("With its guardian gone, the way down opens.");
+    Ok(())
+}
+
+pub const ACTIVE_SEARCH_RADIUS: i32 = 2;
+const PASSIVE_SEARCH_RADIUS: i32 = 1;
+// Chance per turn that a monster right next to the player notices them, before stealth.
+const SLEEPING_NOTICE_CHANCE: f64 = 0.5;
+const AWAKE_NOTICE_CHANCE: f64 = 1.0;
+const NOTICE_ALWAYS_RANGE: f64 = 1.5;
+
+pub fn passive_search(
+    world: &mut World,
+    map: &mut GameMap,
+    _resources: &ResourceManager,
+) -> Result<()> {
+    let Ok(player) = world.get_player_id() else {
+        Err(Error::NoPlayerFound)?
+    };
+
+    if search_for_secrets(player, PASSIVE_SEARCH_RADIUS, false, world, map)? > 0 {
+        logger::log_message("You notice a secret door.");
+    }
+    Ok(())
+}
+
+// Rolls once for every secret door around the searcher and reveals the ones found.
+pub fn search_for_secrets(
+    searcher: usize,
+    radius: i32,
+    deliberate: bool,
+    world: &mut World,
+    map: &mut GameMap,
+) -> Result<usize> {
+    let Some(Position(origin)) = world.borrow_entity_component::<Position>(searcher).copied() else {
+        return Err("Searcher has no position".into());
+    };
+    let Some(attributes) = world.borrow_entity_component::<Attributes>(searcher).copied() else {
+        return Ok(0);
+    };
+    let chance = attributes::search_chance(&attributes, deliberate);
+
+    let secrets: Vec<usize> = (-radius..=radius)
+        .flat_map(|x| (-radius..=radius).map(move |y| origin + Coordinate { x, y }))
+        .flat_map(|coordinate| world.get_entities_at_coordinate(coordinate))
+        .filter(|entity| world.borrow_entity_component::<Secret>(*entity).is_some())
+        .collect();
+
+    let mut found = 0;
+    for secret in secrets {
+        if game_rng().gen_bool(chance) {
+            response_functions::reveal_secret_door(secret, world, map)?;
+            found += 1;
+        }
+    }
+    Ok(found)
+}
+
+// Monsters that have the player in sight may notice them, the more likely the closer and the brighter the player
+// stands and the less stealthy they are. Sleeping monsters get up, awake ones keep track of where the player is.
+pub fn monster_perception(
+    world: &mut World,
+    map: &mut GameMap,
+    resources: &ResourceManager,
+) -> Result<()> {
+    let Ok(player) = world.get_player_id() else {
+        Err(Error::NoPlayerFound)?
+    };
+    let Ok(player_position) = world.get_player_position() else {
+        Err(Error::NoPlayerFound)?
+    };
+
+    let stealth = world
+        .borrow_entity_component::<Attributes>(player)
+        .map_or(0.0, attributes::stealth);
+    let spotting_range = lighting::spotting_range(map.get_light_level(player_position)).min(fov::FOV_RADIUS as f64);
+
+    let Some(behavior_components) = world.borrow_component_vec::<Behavior>() else {
+        return Err("No behavior components registered".into());
+    };
+    let monsters: Vec<(usize, BehaviorState, Coordinate)> = behavior_components
+        .iter()
+        .enumerate()
+        .filter_map(|(entity, behavior)| {
+            let state = behavior.as_ref()?.state;
+            let Position(position) = world.borrow_entity_component::<Position>(entity)?;
+            Some((entity, state, *position))
+        })
+        .collect();
+
+    for (entity, state, position) in monsters {
+        let distance = position.distance(player_position);
+        if distance > spotting_range || !line_of_sight(position, player_position, map, world, resources) {
+            continue;
+        }
+
+        let base_chance = match state {
+            BehaviorState::Asleep => SLEEPING_NOTICE_CHANCE,
+            BehaviorState::Awake => AWAKE_NOTICE_CHANCE,
+            BehaviorState::Alerted => continue,
+        };
+        let chance = base_chance * (1.0 - distance / (spotting_range + 1.0)) * (1.0 - stealth);
+
+        // nobody loses track of someone standing right next to them
+        let noticed = match state {
+            BehaviorState::Awake if distance <= NOTICE_ALWAYS_RANGE => true,
+            _ => game_rng().gen_bool(chance.clamp(0.0, 1.0)),
+        };
+        if noticed {
+            stealth_events::alert(entity, player_position, world, map)?;
+        }
+    }
+
+    Ok(())
+}
+
 pub fn monster_turns(
     world: &mut World,
     map: &mut GameMap,
@@ -108,4 +301,115 @@ pub fn spell_cooldowns(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+// Hurts everything standing on damaging ground and burns down the status effects on every unit.
+pub fn terrain_effects(
+    world: &mut World,
+    map: &mut GameMap,
+    resources: &ResourceManager,
+) -> Result<()> {
+    let Some(health_components) = world.borrow_component_vec::<Health>() else {
+        return Ok(());
+    };
+
+    let entities: Vec<usize> = health_components
+        .iter()
+        .enumerate()
+        .filter_map(|(entity, component)| component.as_ref().map(|_| entity))
+        .collect();
+
+    for entity in entities {
+        let Some(Position(position)) = world.borrow_entity_component::<Position>(entity).copied() else {
+            continue;
+        };
+
+        if !world.get_movement(entity).fly {
+            if let Some(tile) = map.get_tile_at(position, resources) {
+                if tile.damage > 0 {
+                    hurt_entity(entity, tile.damage, tile.damage_type(), &tile.name, world, map);
+                }
+            }
+        }
+
+        let Some(effects) = world.borrow_entity_component_mut::<EffectHandler>(entity) else {
+            continue;
+        };
+        let damaging_effects = effects.damaging_effects();
+        effects.tick_all();
+
+        for effect_type in damaging_effects {
+            hurt_entity(entity, effect_type.damage_per_turn(), effect_type.damage_type(), effect_type.describe(), world, map);
+        }
+    }
+
+    Ok(())
+}
+
+fn hurt_entity(entity: usize, damage: u32, damage_type: DamageType, cause: &str, world: &mut World, map: &GameMap) {
+    let Some(health) = world.borrow_entity_component_mut::<Health>(entity) else {
+        return;
+    };
+    health.sub_current(damage);
+    stats::record_damage(entity, entity, damage_type, damage, world);
+
+    if let Some(stats) = world.borrow_entity_component_mut::<RunStats>(entity) {
+        stats.last_hit_by = Some(cause.to_string());
+    }
+
+    let Some(Position(position)) = world.borrow_entity_component::<Position>(entity) else {
+        return;
+    };
+    if !map.is_tile_visible(*position) {
+        return;
+    }
+
+    if world.get_player_id().ok() == Some(entity) {
+        logger::log_message(&format!("You take {damage} damage from {cause}."));
+    } else if let Some(Name(name)) = world.borrow_entity_component::<Name>(entity) {
+        logger::log_message(&format!("{name} takes {damage} damage from {cause}."));
+    }
+}
+
+// Called whenever a unit steps onto a new tile. Anything flying doesn't touch the ground.
+pub fn enter_tile(
+    entity: usize,
+    world: &mut World,
+    map: &mut GameMap,
+    resources: &ResourceManager,
+) -> Result<()> {
+    if world.get_movement(entity).fly {
+        return Ok(());
+    }
+
+    let Some(Position(position)) = world.borrow_entity_component::<Position>(entity).copied() else {
+        return Err("Entity has no position".into());
+    };
+    let Some(tile) = map.get_tile_at(position, resources) else {
+        return Ok(());
+    };
+
+    if let Some(effect) = tile.status_effect {
+        apply_effect(entity, effect, world)?;
+    }
+
+    if tile.noise > 0 {
+        response_functions::make_noise(tile.noise, entity, world, map, resources)?;
+    }
+
+    Ok(())
+}
+
+pub fn apply_effect(entity: usize, effect: Effect, world: &mut World) -> Result<()> {
+    if world.borrow_entity_component::<Health>(entity).is_none() {
+        return Ok(());
+    }
+
+    if world.borrow_entity_component::<EffectHandler>(entity).is_none() {
+        world.add_component(entity, EffectHandler::default())?;
+    }
+
+    if let Some(effects) = world.borrow_entity_component_mut::<EffectHandler>(entity) {
+        effects.add_effect(effect);
+    }
+    Ok(())
+}
@@ -1,24 +1,31 @@
-use std::rc::Rc;
+use std::{collections::HashMap, rc::Rc};
 
 use log::warn;
-use slint::ModelRc;
+use slint::{Model, ModelRc};
 
 slint::include_modules!();
 
 use crate::{
-    component::{attributes::{Attribute, XpStatus}, health::HealthStatus},
-    game::{self, Game},
+    component::{attributes::{Attribute, XpStatus}, combat::{AttackType, DamageType}, health::HealthStatus, stats::RunStats},
+    game::{Game, TargetMode},
     logger,
-    map::{utils::Coordinate, GameMap},
+    map::{boxextends::BoxExtends, utils::Coordinate, GameMap},
+    morgue::RunOutcome,
     resources::{self, ResourceManager},
 };
 
+const RUN_END_HIGHSCORE_COUNT: usize = 3;
+const RUN_END_KILL_COUNT: usize = 3;
+
+// Size of the map view in tiles. Larger maps scroll along with the player.
+const VIEWPORT_WIDTH: i32 = 32;
+const VIEWPORT_HEIGHT: i32 = 18;
+
 pub fn create_window(game: Game, resources: Rc<ResourceManager>) -> MainWindow {
     let window = MainWindow::new().unwrap();
     window.set_tile_size(resources::TILESET_SIZE);
-    window.set_grid_width(game::MAP_SIZE_X as i32);
-    window.set_grid_height(game::MAP_SIZE_Y as i32);
-
+    window.set_grid_width(VIEWPORT_WIDTH);
+    window.set_grid_height(VIEWPORT_HEIGHT);
     update_game_info(&game, &window);
     window.invoke_display_intro_popup();
     update_tile_map(&game, &window, resources.clone());
@@ -76,6 +83,20 @@ fn set_up_input(mut game: Game, window: &MainWindow, resources: Rc<ResourceManag
                     Err(error) => warn!("{error}"),
                 };
             }
+            InputCommand::NextTarget => {
+                let result = game.next_target_command(target_mode_from(z), &resources);
+                match result {
+                    Ok(_) => {}
+                    Err(error) => warn!("{error}"),
+                };
+            }
+            InputCommand::FireAtTarget => {
+                let result = game.fire_at_target_command(target_mode_from(z), &resources);
+                match result {
+                    Ok(_) => {}
+                    Err(error) => warn!("{error}"),
+                };
+            }
             InputCommand::Descend => {
                 let result = game.descend_command(&resources);
                 match result {
@@ -83,6 +104,13 @@ fn set_up_input(mut game: Game, window: &MainWindow, resources: Rc<ResourceManag
                     Err(error) => warn!("{error}"),
                 };
             }
+            InputCommand::Search => {
+                let result = game.search_command(&resources);
+                match result {
+                    Ok(_) => {}
+                    Err(error) => warn!("{error}"),
+                };
+            }
             InputCommand::Wait => {
                 let result = game.wait_command(&resources);
                 match result {
@@ -112,8 +140,10 @@ fn set_up_input(mut game: Game, window: &MainWindow, resources: Rc<ResourceManag
                 close_window(&weak_window.unwrap());
             }
             InputCommand::Restart => {
+                game.abandon_run(&resources);
                 if let Ok(new_game) = Game::new(&resources) {
                     game = new_game;
+                    logger::clear_history();
                 } else {
                     logger::log_message("Failed to initialize new game.");
                 }
@@ -129,8 +159,22 @@ fn set_up_input(mut game: Game, window: &MainWindow, resources: Rc<ResourceManag
     });
 }
 
+// A selected spell index aims with that spell, otherwise with the ranged attack.
+fn target_mode_from(spell_index: i32) -> TargetMode {
+    match spell_index {
+        index if index >= 0 => TargetMode::Spell(index as usize),
+        _ => TargetMode::Attack(AttackType::Ranged),
+    }
+}
+
 fn display_popup(game: &Game, window: &MainWindow) {
-    if game.player_health_status() == HealthStatus::Dead {
+    if game.get_run_outcome() == Some(RunOutcome::Victory) {
+        let message = generate_run_end_message(game, "You escaped with the artifact!");
+        window.set_victory_message(message.into());
+        window.invoke_display_victory_popup();
+    } else if game.player_health_status() == HealthStatus::Dead {
+        let message = generate_run_end_message(game, "You have died!");
+        window.set_death_message(message.into());
         window.invoke_display_death_popup();
     }
     if game.player_xp_status() == XpStatus::LevelUp {
@@ -138,6 +182,47 @@ fn display_popup(game: &Game, window: &MainWindow) {
     }
 }
 
+fn generate_run_end_message(game: &Game, headline: &str) -> String {
+    let mut lines = vec![headline.to_string()];
+
+    let stats = game.get_run_stats();
+    lines.push(format!("{} turns, {} kills, {} gold", stats.turns, stats.kills, stats.gold_gained));
+    lines.push(format!("Damage dealt {}, taken {}", stats.damage_dealt, stats.damage_taken));
+
+    let damage_by_type: Vec<String> = stats
+        .damage_types
+        .iter()
+        .zip(stats.damage_dealt_by_type.iter().zip(stats.damage_taken_by_type.iter()))
+        .map(|(damage_type, (dealt, taken))| format!("{damage_type}: {dealt} dealt, {taken} taken"))
+        .collect();
+    if !damage_by_type.is_empty() {
+        lines.push(damage_by_type.join("; "));
+    }
+
+    let most_killed: Vec<String> = stats
+        .kill_names
+        .iter()
+        .zip(stats.kill_counts.iter())
+        .take(RUN_END_KILL_COUNT)
+        .map(|(name, count)| format!("{count} {name}"))
+        .collect();
+    if !most_killed.is_empty() {
+        lines.push(format!("Killed {}", most_killed.join(", ")));
+    }
+
+    if let Some((entry, rank)) = game.get_final_score() {
+        lines.push(format!("Score: {} (#{rank})", entry.score));
+    }
+
+    if let Ok(entries) = Game::get_high_scores(RUN_END_HIGHSCORE_COUNT) {
+        for (index, entry) in entries.iter().enumerate() {
+            lines.push(format!("{}. {} {}", index + 1, entry.name, entry.score));
+        }
+    }
+
+    lines.join("\n")
+}
+
 fn display_messages(message_log: &logger::MessageLog, window: &MainWindow) {
     while let Some(msg) = message_log.next_message() {
         window.invoke_display_message(msg.into());
@@ -192,13 +277,18 @@ fn update_game_info(game: &Game, window: &MainWindow) {
 }
 
 fn update_tile_map(game: &Game, window: &MainWindow, resources: Rc<ResourceManager>) {
+    // The map grid reports clicks in view coordinates, the camera offset turns them into map coordinates.
+    let camera = Camera::following(game);
+    window.set_camera_x(camera.view.top_left.x);
+    window.set_camera_y(camera.view.top_left.y);
+
     // Updates frontend's internal data for tiles, which triggers redraw.
     let tiles: Vec<TileGraphics> = game
-        .get_sprite_ids(&resources)
+        .get_sprite_ids_in(camera.view, &resources)
         .into_iter()
-        .map(|vec| Rc::new(slint::VecModel::from(vec)))
-        .map(|vec_model| TileGraphics {
-            image_ids: vec_model.into(),
+        .map(|(images, shade)| TileGraphics {
+            image_ids: Rc::new(slint::VecModel::from(images)).into(),
+            shade,
         })
         .collect();
 
@@ -207,6 +297,38 @@ fn update_tile_map(game: &Game, window: &MainWindow, resources: Rc<ResourceManag
     window.set_memory_tiles(tiles.into());
 }
 
+// The part of the map on screen. Centers on the player, but stops at the edges of the map.
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    pub view: BoxExtends,
+}
+
+impl Camera {
+    pub fn following(game: &Game) -> Self {
+        let MapModel { width, height, .. } = game.get_map_info();
+        let focus = game.get_player_position().unwrap_or_default();
+
+        let top_left = Coordinate {
+            x: Self::scroll(focus.x, VIEWPORT_WIDTH, width),
+            y: Self::scroll(focus.y, VIEWPORT_HEIGHT, height),
+        };
+        let bottom_right = Coordinate {
+            x: top_left.x + VIEWPORT_WIDTH - 1,
+            y: top_left.y + VIEWPORT_HEIGHT - 1,
+        };
+
+        Self {
+            view: BoxExtends { top_left, bottom_right },
+        }
+    }
+
+    // Maps smaller than the view along an axis stay put at the top left.
+    fn scroll(focus: i32, view_size: i32, map_size: i32) -> i32 {
+        let max_offset = (map_size - view_size).max(0);
+        (focus - view_size / 2).clamp(0, max_offset)
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct PlayerModel {
     pub name: String,
@@ -257,4 +379,96 @@ pub struct SpellbookModel {
     pub names: ModelRc<slint::SharedString>,
     pub casts: ModelRc<ModelRc<i32>>,
     pub damages: ModelRc<ModelRc<i32>>,
-}
\ No newline at end of file
+}
+
+// Totals for the run end popup, plus the breakdowns as parallel name and amount lists.
+#[derive(Debug, Clone, Default)]
+pub struct RunStatsModel {
+    pub turns: i32,
+    pub kills: i32,
+    pub spells_cast: i32,
+    pub damage_dealt: i32,
+    pub damage_taken: i32,
+    pub gold_gained: i32,
+    pub doors_opened: i32,
+    pub tiles_explored: i32,
+    pub kill_names: ModelRc<slint::SharedString>,
+    pub kill_counts: ModelRc<i32>,
+    pub spell_names: ModelRc<slint::SharedString>,
+    pub spell_casts: ModelRc<i32>,
+    pub damage_types: ModelRc<slint::SharedString>,
+    pub damage_dealt_by_type: ModelRc<i32>,
+    pub damage_taken_by_type: ModelRc<i32>,
+    pub floor_depths: ModelRc<i32>,
+    pub floor_turns: ModelRc<i32>,
+    pub floor_tiles_explored: ModelRc<i32>,
+}
+
+impl From<&RunStats> for RunStatsModel {
+    fn from(value: &RunStats) -> Self {
+        let (kill_names, kill_counts) = count_models(&value.kills);
+        let (spell_names, spell_casts) = count_models(&value.spells_cast);
+
+        let mut damage_types: Vec<DamageType> = value
+            .damage_dealt
+            .keys()
+            .chain(value.damage_taken.keys())
+            .copied()
+            .collect();
+        damage_types.sort_by_key(|damage_type| format!("{damage_type:?}"));
+        damage_types.dedup();
+
+        let amounts_by_type = |amounts: &HashMap<DamageType, u32>| -> Vec<i32> {
+            damage_types
+                .iter()
+                .map(|damage_type| amounts.get(damage_type).copied().unwrap_or(0) as i32)
+                .collect()
+        };
+        let damage_dealt_by_type = amounts_by_type(&value.damage_dealt);
+        let damage_taken_by_type = amounts_by_type(&value.damage_taken);
+        let damage_type_names: Vec<slint::SharedString> = damage_types
+            .iter()
+            .map(|damage_type| format!("{damage_type:?}").into())
+            .collect();
+
+        let floor_depths: Vec<i32> = value.floors.iter().map(|floor| floor.depth as i32).collect();
+        let floor_turns: Vec<i32> = value.floors.iter().map(|floor| floor.turns as i32).collect();
+        let floor_tiles_explored: Vec<i32> = value
+            .floors
+            .iter()
+            .map(|floor| floor.tiles_explored as i32)
+            .collect();
+
+        Self {
+            turns: value.turns as i32,
+            kills: value.total_kills() as i32,
+            spells_cast: value.spells_cast.values().sum::<u32>() as i32,
+            damage_dealt: value.total_damage_dealt() as i32,
+            damage_taken: value.total_damage_taken() as i32,
+            gold_gained: value.gold_gained as i32,
+            doors_opened: value.doors_opened as i32,
+            tiles_explored: value.total_tiles_explored() as i32,
+            kill_names,
+            kill_counts,
+            spell_names,
+            spell_casts,
+            damage_types: damage_type_names.as_slice().into(),
+            damage_dealt_by_type: damage_dealt_by_type.as_slice().into(),
+            damage_taken_by_type: damage_taken_by_type.as_slice().into(),
+            floor_depths: floor_depths.as_slice().into(),
+            floor_turns: floor_turns.as_slice().into(),
+            floor_tiles_explored: floor_tiles_explored.as_slice().into(),
+        }
+    }
+}
+
+// Most frequent first, ties by name.
+fn count_models(counts: &HashMap<String, u32>) -> (ModelRc<slint::SharedString>, ModelRc<i32>) {
+    let mut entries: Vec<(&String, &u32)> = counts.iter().collect();
+    entries.sort_by(|(a_name, a_count), (b_name, b_count)| b_count.cmp(a_count).then(a_name.cmp(b_name)));
+
+    let names: Vec<slint::SharedString> = entries.iter().map(|(name, _)| name.as_str().into()).collect();
+    let amounts: Vec<i32> = entries.iter().map(|(_, count)| **count as i32).collect();
+
+    (names.as_slice().into(), amounts.as_slice().into())
+}
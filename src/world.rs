@@ -7,6 +7,7 @@ use crate::component::health::Health;
 use crate::component::image::Image;
 use crate::component::items::{Coins, Inventory};
 use crate::component::spell::Spellbook;
+use crate::component::stats::RunStats;
 use crate::component::Name;
 use crate::error::{Error, Result};
 use crate::event::ResponseArguments;
@@ -14,8 +15,8 @@ use crate::map::GameMap;
 use crate::resources::ResourceManager;
 use crate::{
     component::{
-        tags::{Hazard, Player},
-        Collision, Position, SightBlocking,
+        tags::{Artifact, Hazard, Player},
+        Collision, Movement, Position, SightBlocking,
     },
     event::{Event, EventResponse},
     map::{
@@ -45,6 +46,10 @@ impl World {
         }
     }
 
+    pub fn get_bsp(&self) -> &EntityGraph {
+        &self.bsp
+    }
+
     pub fn import_player(&mut self, old_world: &World) -> Result<()> {
         let Ok(old_player) = old_world.get_player_id() else {
             return Err(Error::NoPlayerFound);
@@ -72,6 +77,10 @@ impl World {
             self.add_component(new_player, sight_block.clone())?;
         }
 
+        if let Some(movement) = old_world.borrow_entity_component::<Movement>(old_player) {
+            self.add_component(new_player, *movement)?;
+        }
+
         // COMBAT COMPONENTS
         if let Some(component) = old_world.borrow_entity_component::<Combat>(old_player) {
             self.add_component(new_player, component.clone())?;
@@ -104,6 +113,16 @@ impl World {
             self.add_component(new_player, component.clone())?;
         }
 
+        // RECORDS
+        if let Some(component) = old_world.borrow_entity_component::<RunStats>(old_player) {
+            self.add_component(new_player, component.clone())?;
+        }
+
+        // QUEST ITEMS
+        if let Some(component) = old_world.borrow_entity_component::<Artifact>(old_player) {
+            self.add_component(new_player, *component)?;
+        }
+
         Ok(())
     }
 
@@ -415,6 +434,12 @@ impl World {
         })
     }
 
+    pub fn get_movement(&self, entity: usize) -> Movement {
+        self.borrow_entity_component::<Movement>(entity)
+            .copied()
+            .unwrap_or_default()
+    }
+
     pub fn get_sight_blocking_entity(&self, coordinate: Coordinate) -> Option<usize> {
         let entities = self.get_entities_at_coordinate(coordinate);
         entities.into_iter().find(|entity| {
@@ -425,6 +450,16 @@ impl World {
         })
     }
 
+    pub fn get_sight_obstructing_entity(&self, coordinate: Coordinate) -> Option<usize> {
+        let entities = self.get_entities_at_coordinate(coordinate);
+        entities.into_iter().find(|entity| {
+            matches!(
+                self.borrow_entity_component::<SightBlocking>(*entity),
+                Some(SightBlocking(Los::Obstruct))
+            )
+        })
+    }
+
     pub fn coordinate_has_hazard(&self, coordinate: Coordinate) -> bool {
         let entities = self.get_entities_at_coordinate(coordinate);
         entities